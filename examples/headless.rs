@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+use rain::{DropPool, RainConfig, RainPlugin, Viewport};
+
+fn main() {
+    let mut app = App::build();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(RainPlugin::headless(
+            RainConfig::default(),
+            Viewport::new(1280., 720.),
+        ));
+
+    for frame in 1..=100 {
+        app.app.update();
+        for pool in app.app.world.query::<&DropPool>() {
+            println!(
+                "frame {}: {} drops falling, {} pooled",
+                frame,
+                pool.active(),
+                pool.pooled()
+            );
+        }
+    }
+}
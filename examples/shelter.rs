@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use rain::{Collider, RainPlugin};
+
+fn main() {
+    App::build()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(RainPlugin::default())
+        .add_startup_system(setup.system())
+        .run();
+}
+
+fn setup(commands: &mut Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    let roof = Vec2::new(300., 20.);
+    commands
+        .spawn(SpriteBundle {
+            material: materials.add(Color::rgb(0.4, 0.2, 0.1).into()),
+            sprite: Sprite::new(roof),
+            transform: Transform::from_translation(Vec3::new(-250., -100., 1.)),
+            ..Default::default()
+        })
+        .with(Collider::Box {
+            half_size: roof / 2.,
+        });
+
+    commands
+        .spawn((
+            Transform::from_translation(Vec3::new(250., 0., 0.)),
+            GlobalTransform::default(),
+        ))
+        .with(Collider::Segment {
+            start: Vec2::new(-100., 0.),
+            end: Vec2::new(100., 0.),
+        });
+
+    commands
+        .spawn((
+            Transform::from_translation(Vec3::new(0., -250., 0.)),
+            GlobalTransform::default(),
+        ))
+        .with(Collider::Ground);
+}
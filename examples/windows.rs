@@ -0,0 +1,33 @@
+use bevy::{
+    prelude::*,
+    window::{CreateWindow, WindowId},
+};
+use rain::{Intensity, RainAreaBundle, RainConfig, RainPlugin};
+
+fn main() {
+    App::build()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(RainPlugin::default())
+        .add_startup_system(setup.system())
+        .run();
+}
+
+fn setup(commands: &mut Commands, mut create_window_events: EventWriter<CreateWindow>) {
+    let window = WindowId::new();
+    create_window_events.send(CreateWindow {
+        id: window,
+        descriptor: WindowDescriptor {
+            width: 640.,
+            height: 480.,
+            title: "drizzle".to_string(),
+            ..Default::default()
+        },
+    });
+
+    let config = RainConfig {
+        intensity: Intensity::Drizzle,
+        drop_color: Color::rgb(0.6, 0.6, 0.7),
+        ..Default::default()
+    };
+    commands.spawn(RainAreaBundle::window(window, config, Vec2::zero()));
+}
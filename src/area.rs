@@ -0,0 +1,426 @@
+use crate::{
+    background::{self, BackgroundPipeline},
+    batch::{self, BatchPipeline},
+    Drop, DropMaterials, DropPool, RainConfig, RenderMode, SkyGradient, Splash, Uniforms, Viewport,
+};
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{ActiveCameras, VisibleEntities},
+        pass::{
+            LoadOp, Operations, PassDescriptor, RenderPassDepthStencilAttachmentDescriptor,
+            TextureAttachment,
+        },
+        render_graph::{
+            base::MainPass, CameraNode, PassNode, RenderGraph, WindowSwapChainNode,
+            WindowTextureNode,
+        },
+        texture::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage},
+    },
+    window::WindowId,
+};
+
+/// Rain falling in a window of its own, or inside of a fixed viewport without any window.
+///
+/// An area is an entity with its own `RainConfig`, `Viewport` and `DropPool`,
+/// and once its window exists, its own camera, background and `DropMaterials`.
+/// The camera of an area only draws the drops, splashes, bolts and background of that area.
+/// Weather, wind, lightning, the sky and the time of day are shared by all areas
+/// and follow the `RainConfig` resource the `RainPlugin` was created with.
+///
+/// While its window is missing, the rain of an area is paused.
+/// If the window goes away, the camera, background, drops and splashes of the area go with it,
+/// but the area itself stays and starts over should the window come back.
+pub struct RainArea {
+    /// The window the rain falls in, or `None` if nothing is drawn.
+    pub window: Option<WindowId>,
+    /// Where the camera of the area starts out.
+    pub origin: Vec2,
+    pub(crate) camera: Option<Entity>,
+    pub(crate) background: Option<Entity>,
+    pub(crate) batch: Option<Entity>,
+    /// The fraction of a drop that is left over from the last spawn.
+    pub(crate) accumulator: f32,
+    paused: bool,
+}
+
+impl RainArea {
+    /// The camera the area is seen through, once its window exists.
+    pub fn camera(&self) -> Option<Entity> {
+        self.camera
+    }
+
+    /// The entity with the `Background` of the area, once its window exists.
+    pub fn background(&self) -> Option<Entity> {
+        self.background
+    }
+
+    /// Whether the rain stands still because the window of the area is missing.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+/// Everything an area needs to start raining.
+#[derive(Bundle)]
+pub struct RainAreaBundle {
+    pub area: RainArea,
+    pub config: RainConfig,
+    pub viewport: Viewport,
+    pub pool: DropPool,
+}
+
+impl RainAreaBundle {
+    /// Rain in `window`, seen through a camera that starts out at `origin`.
+    ///
+    /// The camera and background are added as soon as the window exists.
+    /// The settings all areas share are reset in `config`,
+    /// those are the timestep, seed, wind, weather, lightning, sky and day and night.
+    pub fn window(window: WindowId, config: RainConfig, origin: Vec2) -> Self {
+        Self::new(
+            Some(window),
+            own_settings(config),
+            Viewport::default(),
+            origin,
+        )
+    }
+
+    /// Rain inside of `viewport` without anything being drawn.
+    ///
+    /// The settings all areas share are reset in `config`, like for `window`.
+    pub fn headless(config: RainConfig, viewport: Viewport) -> Self {
+        Self::new(None, own_settings(config), viewport, viewport.center)
+    }
+
+    /// The area the `RainPlugin` adds itself, which keeps all of `config`
+    /// since the shared settings come from there.
+    pub(crate) fn main(config: RainConfig, headless: Option<Viewport>) -> Self {
+        match headless {
+            Some(viewport) => Self::new(None, config, viewport, viewport.center),
+            None => Self::new(
+                Some(WindowId::primary()),
+                config,
+                Viewport::default(),
+                Vec2::zero(),
+            ),
+        }
+    }
+
+    fn new(window: Option<WindowId>, config: RainConfig, viewport: Viewport, origin: Vec2) -> Self {
+        Self {
+            area: RainArea {
+                window,
+                origin,
+                camera: None,
+                background: None,
+                batch: None,
+                accumulator: 0.,
+                paused: false,
+            },
+            config,
+            viewport,
+            pool: DropPool::default(),
+        }
+    }
+}
+
+/// `config` with the settings that all areas share reset to their defaults:
+/// the timestep, seed, wind, weather, lightning, sky and time of day.
+///
+/// Those come from the configuration the `RainPlugin` was created with,
+/// so a warning is logged if any of them were changed for a single area.
+fn own_settings(config: RainConfig) -> RainConfig {
+    let shared = RainConfig::default();
+    let changed = config.timestep != shared.timestep
+        || config.seed != shared.seed
+        || config.wind != shared.wind
+        || config.weather != shared.weather
+        || config.lightning != shared.lightning
+        || config.sky != shared.sky
+        || config.day_night != shared.day_night;
+    if changed {
+        warn!(
+            "the timestep, seed, wind, weather, lightning, sky and day and night are shared \
+             by all areas, so they are taken from the configuration of the `RainPlugin`"
+        );
+    }
+    RainConfig {
+        timestep: shared.timestep,
+        seed: shared.seed,
+        wind: shared.wind,
+        weather: shared.weather,
+        lightning: shared.lightning,
+        sky: shared.sky,
+        day_night: shared.day_night,
+        ..config
+    }
+}
+
+/// Marks something drawn for the area in the given entity,
+/// so the cameras of other areas leave it out.
+pub(crate) struct InArea(pub(crate) Entity);
+
+/// Marks the area the `RainPlugin` added itself, which the watched configuration file applies to.
+pub(crate) struct MainArea;
+
+/// Pauses the rain of areas whose window is missing, for example because it is not created yet
+/// or was closed, and resumes it as soon as the window is there.
+pub(crate) fn watch_windows(mut areas: Query<&mut RainArea>, windows: Res<Windows>) {
+    for mut area in areas.iter_mut() {
+        let window = match area.window {
+            Some(window) => window,
+            None => continue,
+        };
+        let missing = windows.get(window).is_none();
+        if missing != area.paused {
+            if missing {
+                info!("pausing the rain until window {:?} is there", window);
+            } else {
+                info!("window {:?} is back, resuming the rain", window);
+            }
+            area.paused = missing;
+        }
+    }
+}
+
+/// Gives areas whose window exists a camera, a background and materials to draw the drops with.
+pub(crate) fn setup_areas(
+    commands: &mut Commands,
+    mut areas: Query<(Entity, &mut RainArea, &RainConfig)>,
+    windows: Res<Windows>,
+    mut active_cameras: ResMut<ActiveCameras>,
+    mut render_graph: ResMut<RenderGraph>,
+    msaa: Res<Msaa>,
+    clear_color: Res<ClearColor>,
+    background_pipeline: Res<BackgroundPipeline>,
+    batch_pipeline: Res<BatchPipeline>,
+    mut uniforms: ResMut<Assets<Uniforms>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    sky: Res<SkyGradient>,
+) {
+    for (entity, mut area, config) in areas.iter_mut() {
+        if area.camera.is_some() {
+            continue;
+        }
+        let window = match area.window.and_then(|window| windows.get(window)) {
+            Some(window) => window,
+            None => continue,
+        };
+
+        let mut camera = OrthographicCameraBundle::new_2d();
+        if !window.id().is_primary() {
+            let name = camera_name(entity);
+            add_window_pass(&mut render_graph, &msaa, &clear_color, window.id(), &name);
+            active_cameras.add(&name);
+            camera.camera.name = Some(name);
+            camera.camera.window = window.id();
+        }
+        camera.transform.translation.x = area.origin.x;
+        camera.transform.translation.y = area.origin.y;
+        area.camera = commands.spawn(camera).current_entity();
+
+        let background =
+            background::spawn(commands, &background_pipeline, &mut uniforms, window, &sky);
+        commands.insert_one(background, InArea(entity));
+        area.background = Some(background);
+        if config.render_mode == RenderMode::Batched {
+            let batch = batch::spawn_batch(commands, &batch_pipeline, &mut meshes, entity);
+            commands.insert_one(batch, InArea(entity));
+            area.batch = Some(batch);
+        }
+        commands.insert_one(entity, DropMaterials::new(&mut materials, config));
+    }
+}
+
+/// Leaves what was drawn for other areas out of what the camera of each area sees,
+/// so areas in different windows don't draw each other's rain.
+///
+/// This runs after Bevy found the entities each camera sees and before they are drawn.
+/// Entities that are not part of any area are still seen by every camera.
+pub(crate) fn separate_areas(
+    areas: Query<(Entity, &RainArea)>,
+    mut cameras: Query<&mut VisibleEntities>,
+    parts: Query<&InArea>,
+) {
+    for (entity, area) in areas.iter() {
+        let camera = match area.camera {
+            Some(camera) => camera,
+            None => continue,
+        };
+        if let Ok(mut visible_entities) = cameras.get_mut(camera) {
+            visible_entities.value.retain(|visible| {
+                parts
+                    .get(visible.entity)
+                    .map_or(true, |part| part.0 == entity)
+            });
+        }
+    }
+}
+
+/// The name of the camera of an area in a window other than the primary one.
+fn camera_name(area: Entity) -> String {
+    format!("rain_camera_{}", area.id())
+}
+
+/// Draws what the camera named `camera` sees into `window`,
+/// the same way Bevy's main pass does for the primary window.
+fn add_window_pass(
+    render_graph: &mut RenderGraph,
+    msaa: &Msaa,
+    clear_color: &ClearColor,
+    window: WindowId,
+    camera: &str,
+) {
+    let pass_node = format!("{}_pass", camera);
+    let swap_chain_node = format!("{}_swap_chain", camera);
+    let depth_node = format!("{}_depth", camera);
+
+    render_graph.add_system_node(camera.to_string(), CameraNode::new(camera));
+
+    let mut pass = PassNode::<&MainPass>::new(PassDescriptor {
+        color_attachments: vec![msaa.color_attachment_descriptor(
+            TextureAttachment::Input("color_attachment".to_string()),
+            TextureAttachment::Input("color_resolve_target".to_string()),
+            Operations {
+                load: LoadOp::Clear(clear_color.0),
+                store: true,
+            },
+        )],
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+            attachment: TextureAttachment::Input("depth".to_string()),
+            depth_ops: Some(Operations {
+                load: LoadOp::Clear(1.),
+                store: true,
+            }),
+            stencil_ops: None,
+        }),
+        sample_count: msaa.samples,
+    });
+    pass.add_camera(camera);
+    render_graph.add_node(pass_node.clone(), pass);
+
+    render_graph.add_node(swap_chain_node.clone(), WindowSwapChainNode::new(window));
+    render_graph.add_node(
+        depth_node.clone(),
+        WindowTextureNode::new(
+            window,
+            TextureDescriptor {
+                format: TextureFormat::Depth32Float,
+                usage: TextureUsage::OUTPUT_ATTACHMENT,
+                sample_count: msaa.samples,
+                ..Default::default()
+            },
+        ),
+    );
+
+    let color_slot = if msaa.samples > 1 {
+        "color_resolve_target"
+    } else {
+        "color_attachment"
+    };
+    render_graph
+        .add_slot_edge(
+            swap_chain_node,
+            WindowSwapChainNode::OUT_TEXTURE,
+            pass_node.clone(),
+            color_slot,
+        )
+        .unwrap();
+    render_graph
+        .add_slot_edge(
+            depth_node,
+            WindowTextureNode::OUT_TEXTURE,
+            pass_node.clone(),
+            "depth",
+        )
+        .unwrap();
+    render_graph
+        .add_node_edge(camera, pass_node.clone())
+        .unwrap();
+    render_graph
+        .add_node_edge(background::UNIFORMS_NODE, pass_node.clone())
+        .unwrap();
+
+    if msaa.samples > 1 {
+        let multisampled_node = format!("{}_multisampled_color", camera);
+        render_graph.add_node(
+            multisampled_node.clone(),
+            WindowTextureNode::new(
+                window,
+                TextureDescriptor {
+                    size: Extent3d::new(1, 1, 1),
+                    mip_level_count: 1,
+                    sample_count: msaa.samples,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::default(),
+                    usage: TextureUsage::OUTPUT_ATTACHMENT,
+                },
+            ),
+        );
+        render_graph
+            .add_slot_edge(
+                multisampled_node,
+                WindowTextureNode::OUT_TEXTURE,
+                pass_node,
+                "color_attachment",
+            )
+            .unwrap();
+    }
+}
+
+/// Removes the nodes `add_window_pass` added for the camera named `camera`.
+fn remove_window_pass(render_graph: &mut RenderGraph, camera: &str) {
+    for node in &["_pass", "_swap_chain", "_depth", "_multisampled_color", ""] {
+        // Without MSAA there is no multisampled color node, which is fine to skip.
+        let _ = render_graph.remove_node(format!("{}{}", camera, node));
+    }
+}
+
+/// Takes down the camera, background, drops and splashes of areas whose window went away.
+///
+/// The area itself stays and is paused by `watch_windows`,
+/// so it is set up again from scratch should its window come back.
+pub(crate) fn close_areas(
+    commands: &mut Commands,
+    mut areas: Query<(Entity, &mut RainArea, &mut DropPool)>,
+    drops: Query<(Entity, &Drop)>,
+    splashes: Query<(Entity, &Splash)>,
+    windows: Res<Windows>,
+    mut active_cameras: ResMut<ActiveCameras>,
+    mut render_graph: ResMut<RenderGraph>,
+) {
+    for (entity, mut area, mut pool) in areas.iter_mut() {
+        let window = match area.window {
+            Some(window) if area.camera.is_some() && windows.get(window).is_none() => window,
+            _ => continue,
+        };
+        if !window.is_primary() {
+            let name = camera_name(entity);
+            active_cameras.remove(&name);
+            remove_window_pass(&mut render_graph, &name);
+        }
+        let parts = [
+            area.camera.take(),
+            area.background.take(),
+            area.batch.take(),
+        ];
+        for part in parts.iter().flatten() {
+            commands.despawn(*part);
+        }
+        commands.remove_one::<DropMaterials>(entity);
+
+        for (drop_entity, drop) in drops.iter() {
+            if drop.area == entity {
+                commands.despawn(drop_entity);
+            }
+        }
+        *pool = DropPool::default();
+        area.accumulator = 0.;
+        for (splash_entity, splash) in splashes.iter() {
+            if splash.area == entity {
+                commands.despawn(splash_entity);
+            }
+        }
+    }
+}
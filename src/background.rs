@@ -0,0 +1,255 @@
+use crate::{RainArea, SkyGradient, Viewport};
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        pipeline::PipelineDescriptor,
+        render_graph::{base, AssetRenderResourcesNode, RenderGraph},
+        renderer::RenderResources,
+        shader::{ShaderStage, ShaderStages},
+    },
+    window::WindowId,
+};
+
+const VERTEX_SHADER: &str = r#"
+#version 460
+layout(location = 0) in vec3 Vertex_Position;
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 ViewProj;
+};
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+void main() {
+    gl_Position = ViewProj * Model * vec4(Vertex_Position, 1.);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 460
+layout(location = 0) out vec4 o_Target;
+layout(set = 2, binding = 0) uniform Uniforms_size {
+    vec2 size;
+};
+layout(set = 2, binding = 1) uniform Uniforms_colors {
+    mat4 colors;
+};
+layout(set = 2, binding = 2) uniform Uniforms_stops {
+    vec4 stops;
+};
+layout(set = 2, binding = 3) uniform Uniforms_flash {
+    float flash;
+};
+void main() {
+    vec2 position = gl_FragCoord.xy / size;
+
+    vec4 sky = colors[0];
+    for (int i = 1; i < 4; i++) {
+        if (position.y > stops[i - 1]) {
+            float t = (position.y - stops[i - 1]) / max(stops[i] - stops[i - 1], 0.0001);
+            sky = mix(colors[i - 1], colors[i], clamp(t, 0., 1.));
+        }
+    }
+    o_Target = mix(sky, vec4(1., 1., 1., 1.), flash);
+}
+"#;
+
+/// The sprite the sky gradient is drawn on, covering `window`.
+///
+/// Each background has its own `Handle<Uniforms>` and only that one is updated.
+/// The sprite is a unit quad scaled to the logical size of the window,
+/// while its `Uniforms` get the physical size because that is what `gl_FragCoord` is in.
+#[derive(Clone, Copy, Debug)]
+pub struct Background {
+    pub window: WindowId,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self {
+            window: WindowId::primary(),
+        }
+    }
+}
+
+#[derive(RenderResources, TypeUuid)]
+#[uuid = "5cea8a14-f045-4884-b833-1e616ddf29ac"]
+pub struct Uniforms {
+    /// The size of the window in physical pixels.
+    pub size: Vec2,
+    /// The colours of the `SkyGradient` as columns in linear RGBA.
+    pub colors: Mat4,
+    /// Where each of the `colors` is, from 0 at the bottom to 1 at the top.
+    pub stops: Vec4,
+    /// How much the sky is lit up by lightning, from 0 to 1.
+    pub flash: f32,
+}
+
+/// The render graph node that uploads the `Uniforms` of every background.
+pub(crate) const UNIFORMS_NODE: &str = "size";
+
+/// The pipeline every background is drawn with.
+pub(crate) struct BackgroundPipeline(Handle<PipelineDescriptor>);
+
+pub(crate) fn setup(
+    commands: &mut Commands,
+    mut pipelines: ResMut<Assets<PipelineDescriptor>>,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut render_graph: ResMut<RenderGraph>,
+) {
+    let pipeline_handle = pipelines.add(PipelineDescriptor::default_config(ShaderStages {
+        vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER)),
+        fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER))),
+    }));
+
+    render_graph.add_system_node(
+        UNIFORMS_NODE,
+        AssetRenderResourcesNode::<Uniforms>::new(true),
+    );
+
+    render_graph
+        .add_node_edge(UNIFORMS_NODE, base::node::MAIN_PASS)
+        .unwrap();
+
+    commands.insert_resource(BackgroundPipeline(pipeline_handle));
+}
+
+/// Spawns a background covering `window` with uniforms of its own.
+pub(crate) fn spawn(
+    commands: &mut Commands,
+    pipeline: &BackgroundPipeline,
+    uniforms: &mut Assets<Uniforms>,
+    window: &Window,
+    sky: &SkyGradient,
+) -> Entity {
+    let (transform, size) = cover(window, &Viewport::default(), Transform::default());
+
+    let (colors, stops) = sky.pack();
+    let uniform = uniforms.add(Uniforms {
+        size,
+        colors,
+        stops,
+        flash: 0.,
+    });
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite::new(Vec2::one()),
+            render_pipelines: RenderPipelines::from_handles(&vec![pipeline.0.clone()]),
+            transform,
+            ..Default::default()
+        })
+        .with(uniform)
+        .with(Background {
+            window: window.id(),
+        })
+        .current_entity()
+        .unwrap()
+}
+
+/// Keeps the background covering the window when it is resized or moved to another screen,
+/// and in front of the camera when it moves or zooms.
+pub(crate) fn update_background(
+    windows: Res<Windows>,
+    areas: Query<(&RainArea, &Viewport)>,
+    mut background_query: Query<(&Background, &mut Transform, &Handle<Uniforms>)>,
+    mut uniforms: ResMut<Assets<Uniforms>>,
+) {
+    for (area, viewport) in areas.iter() {
+        let entity = match area.background() {
+            Some(entity) => entity,
+            None => continue,
+        };
+        let (background, mut transform, handle) = match background_query.get_mut(entity) {
+            Ok(background) => background,
+            Err(_) => continue,
+        };
+        let window = match windows.get(background.window) {
+            Some(window) => window,
+            None => continue,
+        };
+        let (covering, size) = cover(window, viewport, *transform);
+        if *transform != covering {
+            *transform = covering;
+        }
+        let resized = uniforms
+            .get(handle)
+            .map_or(false, |uniform| uniform.size != size);
+        if resized {
+            if let Some(uniform) = uniforms.get_mut(handle) {
+                uniform.size = size;
+            }
+        }
+    }
+}
+
+/// The transform of a background covering `window`, or what the camera sees in `viewport`
+/// once it is known, and the size of the window in physical pixels for its `Uniforms`.
+fn cover(window: &Window, viewport: &Viewport, transform: Transform) -> (Transform, Vec2) {
+    let mut transform = transform;
+    if viewport.width > 0. && viewport.height > 0. {
+        transform.scale = Vec3::new(viewport.width, viewport.height, 1.);
+        transform.translation = viewport.center.extend(transform.translation.z);
+    } else {
+        transform.scale = Vec3::new(window.width(), window.height(), 1.);
+    }
+    let size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    (transform, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::window::WindowId;
+
+    fn window(physical_width: u32, physical_height: u32, scale_factor: f64) -> Window {
+        Window::new(
+            WindowId::primary(),
+            &WindowDescriptor::default(),
+            physical_width,
+            physical_height,
+            scale_factor,
+        )
+    }
+
+    #[test]
+    fn covers_the_window_after_setup() {
+        for &scale_factor in &[1., 2.] {
+            let window = window(1280, 720, scale_factor);
+            let (transform, size) = cover(&window, &Viewport::default(), Transform::default());
+            let factor = scale_factor as f32;
+            assert_eq!(
+                transform.scale,
+                Vec3::new(1280. / factor, 720. / factor, 1.)
+            );
+            assert_eq!(size, Vec2::new(1280., 720.));
+        }
+    }
+
+    #[test]
+    fn covers_the_window_after_resize() {
+        for &scale_factor in &[1., 2.] {
+            let mut window = window(1280, 720, scale_factor);
+            let (transform, _) = cover(&window, &Viewport::default(), Transform::default());
+            window.update_actual_size_from_backend(800, 600);
+            let (transform, size) = cover(&window, &Viewport::default(), transform);
+            let factor = scale_factor as f32;
+            assert_eq!(transform.scale, Vec3::new(800. / factor, 600. / factor, 1.));
+            assert_eq!(size, Vec2::new(800., 600.));
+        }
+    }
+
+    #[test]
+    fn follows_the_viewport_of_the_camera() {
+        let window = window(2560, 1440, 2.);
+        let viewport = Viewport::new(2560., 1440.).with_center(Vec2::new(100., -50.));
+        let behind = Transform::from_translation(Vec3::new(0., 0., 0.5));
+        let (transform, size) = cover(&window, &viewport, behind);
+        assert_eq!(transform.scale, Vec3::new(2560., 1440., 1.));
+        assert_eq!(transform.translation, Vec3::new(100., -50., 0.5));
+        assert_eq!(size, Vec2::new(2560., 1440.));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{palette, Drop, DropMaterials, RainConfig, Velocity, Wind};
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::Indices,
+        pipeline::{PipelineDescriptor, PrimitiveTopology},
+        shader::{ShaderStage, ShaderStages},
+    },
+};
+
+const VERTEX_SHADER: &str = r#"
+#version 460
+layout(location = 0) in vec2 Vertex_Corner;
+layout(location = 1) in vec3 Drop_Position;
+layout(location = 2) in vec2 Drop_Size;
+layout(location = 3) in float Drop_Angle;
+layout(location = 4) in vec4 Drop_Color;
+layout(location = 0) out vec4 v_Color;
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 ViewProj;
+};
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+void main() {
+    vec2 corner = Vertex_Corner * Drop_Size;
+    float s = sin(Drop_Angle);
+    float c = cos(Drop_Angle);
+    vec2 offset = vec2(c * corner.x - s * corner.y, s * corner.x + c * corner.y);
+    v_Color = Drop_Color;
+    gl_Position = ViewProj * Model * vec4(Drop_Position.xy + offset, Drop_Position.z, 1.);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 460
+layout(location = 0) in vec4 v_Color;
+layout(location = 0) out vec4 o_Target;
+void main() {
+    o_Target = v_Color;
+}
+"#;
+
+const CORNERS: [[f32; 2]; 4] = [[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]];
+const CORNER_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+/// Everything needed to draw a single drop as a quad of the batch.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DropQuad {
+    /// Where the drop is, with z being the depth of its layer.
+    pub position: [f32; 3],
+    /// The width and the length of the drop.
+    pub size: [f32; 2],
+    /// The rotation around the z axis in radians.
+    pub angle: f32,
+    pub color: [f32; 4],
+}
+
+/// The vertex data of a batch of drops, ready to be put into a `Mesh`.
+///
+/// The data of every drop is repeated for each of the four corners of its quad,
+/// so all drops are drawn as one mesh with a single draw call.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchBuffer {
+    pub corners: Vec<[f32; 2]>,
+    pub positions: Vec<[f32; 3]>,
+    pub sizes: Vec<[f32; 2]>,
+    pub angles: Vec<f32>,
+    pub colors: Vec<[f32; 4]>,
+    pub indices: Vec<u32>,
+}
+
+impl BatchBuffer {
+    pub fn pack(quads: &[DropQuad]) -> Self {
+        let vertices = quads.len() * CORNERS.len();
+        let mut buffer = Self {
+            corners: Vec::with_capacity(vertices),
+            positions: Vec::with_capacity(vertices),
+            sizes: Vec::with_capacity(vertices),
+            angles: Vec::with_capacity(vertices),
+            colors: Vec::with_capacity(vertices),
+            indices: Vec::with_capacity(quads.len() * CORNER_INDICES.len()),
+        };
+        for (i, quad) in quads.iter().enumerate() {
+            let first = (i * CORNERS.len()) as u32;
+            buffer
+                .indices
+                .extend(CORNER_INDICES.iter().map(|index| first + index));
+            for corner in CORNERS.iter() {
+                buffer.corners.push(*corner);
+                buffer.positions.push(quad.position);
+                buffer.sizes.push(quad.size);
+                buffer.angles.push(quad.angle);
+                buffer.colors.push(quad.color);
+            }
+        }
+        buffer
+    }
+
+    /// How many drops are in this buffer.
+    pub fn len(&self) -> usize {
+        self.corners.len() / CORNERS.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.corners.is_empty()
+    }
+
+    fn write_to(self, mesh: &mut Mesh) {
+        mesh.set_attribute("Vertex_Corner", self.corners);
+        mesh.set_attribute("Drop_Position", self.positions);
+        mesh.set_attribute("Drop_Size", self.sizes);
+        mesh.set_attribute("Drop_Angle", self.angles);
+        mesh.set_attribute("Drop_Color", self.colors);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+    }
+}
+
+/// The mesh all drops of an area are drawn with.
+pub(crate) struct DropBatch {
+    area: Entity,
+}
+
+/// The pipeline every `DropBatch` is drawn with.
+pub(crate) struct BatchPipeline(Handle<PipelineDescriptor>);
+
+pub(crate) fn setup(
+    commands: &mut Commands,
+    mut pipelines: ResMut<Assets<PipelineDescriptor>>,
+    mut shaders: ResMut<Assets<Shader>>,
+) {
+    let pipeline_handle = pipelines.add(PipelineDescriptor::default_config(ShaderStages {
+        vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, VERTEX_SHADER)),
+        fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, FRAGMENT_SHADER))),
+    }));
+    commands.insert_resource(BatchPipeline(pipeline_handle));
+}
+
+/// Spawns the batch the drops of `area` are drawn with.
+pub(crate) fn spawn_batch(
+    commands: &mut Commands,
+    pipeline: &BatchPipeline,
+    meshes: &mut Assets<Mesh>,
+    area: Entity,
+) -> Entity {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    BatchBuffer::default().write_to(&mut mesh);
+
+    commands
+        .spawn(MeshBundle {
+            mesh: meshes.add(mesh),
+            render_pipelines: RenderPipelines::from_handles(&vec![pipeline.0.clone()]),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .with(DropBatch { area })
+        .current_entity()
+        .unwrap()
+}
+
+/// Rebuilds the mesh of every batch from the drops of its area.
+pub(crate) fn update_batch(
+    drops: Query<(&Drop, &Velocity, &Transform)>,
+    mut batches: Query<(&DropBatch, &Handle<Mesh>, &mut Visible)>,
+    areas: Query<(&RainConfig, &DropMaterials)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (batch, mesh, mut visible) in batches.iter_mut() {
+        let (config, drop_materials) = match areas.get(batch.area) {
+            Ok(area) => area,
+            Err(_) => continue,
+        };
+        let quads = drops
+            .iter()
+            .filter(|(drop, _, _)| drop.is_active() && drop.area == batch.area)
+            .map(|(drop, velocity, transform)| {
+                let color = palette::shade(drop_materials.layer_color(drop.layer), drop.size);
+                DropQuad {
+                    position: transform.translation.into(),
+                    size: [
+                        config.drop_width * config.layer(drop.layer).size,
+                        drop.length,
+                    ],
+                    angle: Wind::angle(velocity.0),
+                    color: [color.r(), color.g(), color.b(), color.a()],
+                }
+            })
+            .collect::<Vec<_>>();
+        let buffer = BatchBuffer::pack(&quads);
+
+        visible.is_visible = !buffer.is_empty();
+        if let Some(mesh) = meshes.get_mut(mesh) {
+            buffer.write_to(mesh);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad(x: f32) -> DropQuad {
+        DropQuad {
+            position: [x, 2., 3.],
+            size: [2., 50.],
+            angle: 0.1,
+            color: [0.3, 0.3, 0.75, 1.],
+        }
+    }
+
+    #[test]
+    fn empty_buffer() {
+        let buffer = BatchBuffer::pack(&[]);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.len(), 0);
+        assert!(buffer.indices.is_empty());
+        assert_eq!(buffer, BatchBuffer::default());
+    }
+
+    #[test]
+    fn every_drop_is_copied_to_its_four_corners() {
+        let quads = [quad(1.), quad(10.)];
+        let buffer = BatchBuffer::pack(&quads);
+        assert!(!buffer.is_empty());
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.corners.len(), 8);
+        for (i, quad) in quads.iter().enumerate() {
+            for vertex in i * 4..(i + 1) * 4 {
+                assert_eq!(buffer.corners[vertex], CORNERS[vertex - i * 4]);
+                assert_eq!(buffer.positions[vertex], quad.position);
+                assert_eq!(buffer.sizes[vertex], quad.size);
+                assert_eq!(buffer.angles[vertex], quad.angle);
+                assert_eq!(buffer.colors[vertex], quad.color);
+            }
+        }
+    }
+
+    #[test]
+    fn indices_are_offset_by_four_per_drop() {
+        let buffer = BatchBuffer::pack(&[quad(1.), quad(2.), quad(3.)]);
+        assert_eq!(
+            buffer.indices,
+            vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7, 8, 9, 10, 8, 10, 11]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use bevy::{prelude::*, window::WindowMode};
+use rain::Intensity;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: rain [options]
+
+Options:
+    --size <width>x<height>  Size of the window in pixels [default: 1280x720]
+    --fullscreen             Fill the screen, changing its video mode
+    --borderless             Fill the screen with a borderless window
+    --no-vsync               Draw frames as fast as possible
+    --title <title>          Title of the window [default: rain]
+    --seed <number>          Seed of the rain, also read from RAIN_SEED
+    --config <path>          Configuration file to load and watch [default: rain.ron]
+    --intensity <intensity>  drizzle, shower, downpour or drops per second per 100x100 pixels
+    -h, --help               Print this help
+";
+
+/// What the demo was asked to do on the command line.
+pub enum Command {
+    Run(Options),
+    Help,
+}
+
+pub struct Options {
+    pub window: WindowDescriptor,
+    /// The configuration file given with `--config`, if any.
+    pub config: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub intensity: Option<Intensity>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            window: WindowDescriptor {
+                width: 1280.,
+                height: 720.,
+                title: "rain".to_string(),
+                ..Default::default()
+            },
+            config: None,
+            seed: None,
+            intensity: None,
+        }
+    }
+}
+
+/// Parses the arguments after the name of the program.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--size" => {
+                let (width, height) = size(&value()?)?;
+                options.window.width = width;
+                options.window.height = height;
+            }
+            "--fullscreen" => options.window.mode = WindowMode::Fullscreen { use_size: false },
+            "--borderless" => options.window.mode = WindowMode::BorderlessFullscreen,
+            "--no-vsync" => options.window.vsync = false,
+            "--title" => options.window.title = value()?,
+            "--seed" => {
+                let seed = value()?;
+                options.seed = Some(
+                    seed.parse()
+                        .map_err(|_| format!("`{}` is not a valid seed", seed))?,
+                );
+            }
+            "--config" => options.config = Some(value()?.into()),
+            "--intensity" => options.intensity = Some(intensity(&value()?)?),
+            _ => return Err(format!("unknown option `{}`", arg)),
+        }
+    }
+    Ok(Command::Run(options))
+}
+
+fn size(size: &str) -> Result<(f32, f32), String> {
+    let invalid = || format!("`{}` is not a size like 1280x720", size);
+    let (width, height) = match size.find('x') {
+        Some(x) => (&size[..x], &size[x + 1..]),
+        None => return Err(invalid()),
+    };
+    match (width.parse::<u32>(), height.parse::<u32>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width as f32, height as f32)),
+        _ => Err(invalid()),
+    }
+}
+
+fn intensity(intensity: &str) -> Result<Intensity, String> {
+    match intensity {
+        "drizzle" => Ok(Intensity::Drizzle),
+        "shower" => Ok(Intensity::Shower),
+        "downpour" => Ok(Intensity::Downpour),
+        _ => match intensity.parse::<f32>() {
+            Ok(density) if density >= 0. && density.is_finite() => Ok(Intensity::Density(density)),
+            _ => Err(format!(
+                "`{}` is not drizzle, shower, downpour or a number of drops",
+                intensity
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match parse_args(args) {
+            Ok(Command::Run(options)) => options,
+            Ok(Command::Help) => panic!("{:?} asked for help", args),
+            Err(err) => panic!("{:?} failed: {}", args, err),
+        }
+    }
+
+    fn error(args: &[&str]) -> String {
+        match parse_args(args) {
+            Err(err) => err,
+            Ok(_) => panic!("{:?} was accepted", args),
+        }
+    }
+
+    #[test]
+    fn no_options_run_with_the_defaults() {
+        let options = options(&[]);
+        assert_eq!(options.window.width, 1280.);
+        assert_eq!(options.window.height, 720.);
+        assert!(options.config.is_none());
+        assert!(options.seed.is_none());
+        assert!(options.intensity.is_none());
+    }
+
+    #[test]
+    fn options_are_parsed() {
+        let options = options(&[
+            "--size",
+            "1920x1080",
+            "--seed",
+            "42",
+            "--intensity",
+            "2.5",
+            "--config",
+            "storm.ron",
+        ]);
+        assert_eq!(options.window.width, 1920.);
+        assert_eq!(options.window.height, 1080.);
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.intensity, Some(Intensity::Density(2.5)));
+        assert_eq!(options.config, Some(PathBuf::from("storm.ron")));
+    }
+
+    #[test]
+    fn intensities_can_be_named() {
+        let named = [
+            ("drizzle", Intensity::Drizzle),
+            ("shower", Intensity::Shower),
+            ("downpour", Intensity::Downpour),
+        ];
+        for &(name, intensity) in &named {
+            assert_eq!(options(&["--intensity", name]).intensity, Some(intensity));
+        }
+    }
+
+    #[test]
+    fn sizes_need_a_width_and_a_height_above_zero() {
+        assert_eq!(
+            error(&["--size", "0x5"]),
+            "`0x5` is not a size like 1280x720"
+        );
+        assert_eq!(
+            error(&["--size", "5x0"]),
+            "`5x0` is not a size like 1280x720"
+        );
+        assert_eq!(
+            error(&["--size", "1280"]),
+            "`1280` is not a size like 1280x720"
+        );
+    }
+
+    #[test]
+    fn options_need_their_value() {
+        assert_eq!(error(&["--size"]), "`--size` needs a value");
+        assert_eq!(error(&["--no-vsync", "--seed"]), "`--seed` needs a value");
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        assert_eq!(error(&["--fast"]), "unknown option `--fast`");
+    }
+
+    #[test]
+    fn seeds_are_numbers() {
+        assert_eq!(error(&["--seed", "rain"]), "`rain` is not a valid seed");
+        assert_eq!(error(&["--seed", "-1"]), "`-1` is not a valid seed");
+    }
+
+    #[test]
+    fn intensities_are_named_or_finite_and_not_negative() {
+        for &intensity in &["-1", "nan", "inf", "heavy"] {
+            assert_eq!(
+                error(&["--intensity", intensity]),
+                format!(
+                    "`{}` is not drizzle, shower, downpour or a number of drops",
+                    intensity
+                )
+            );
+        }
+    }
+}
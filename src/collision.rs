@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+
+/// A surface drops collide with, placed at the translation of its entity.
+///
+/// Rotation and scale of the entity are ignored.
+#[derive(Clone, Copy, Debug)]
+pub enum Collider {
+    /// Everything below the entity.
+    Ground,
+    /// An axis-aligned box extending `half_size` in every direction, like a rooftop.
+    Box { half_size: Vec2 },
+    /// A line from `start` to `end`, like an umbrella.
+    Segment { start: Vec2, end: Vec2 },
+}
+
+impl Collider {
+    /// Returns how far along the way from `from` to `to` something at `origin` is hit,
+    /// from 0 at `from` to 1 at `to`.
+    pub fn hit(&self, origin: Vec2, from: Vec2, to: Vec2) -> Option<f32> {
+        match *self {
+            Collider::Ground => {
+                if from.y >= origin.y && to.y < origin.y {
+                    Some((from.y - origin.y) / (from.y - to.y))
+                } else {
+                    None
+                }
+            }
+            Collider::Box { half_size } => {
+                hit_box(origin - half_size, origin + half_size, from, to)
+            }
+            Collider::Segment { start, end } => hit_segment(origin + start, origin + end, from, to),
+        }
+    }
+}
+
+fn hit_box(min: Vec2, max: Vec2, from: Vec2, to: Vec2) -> Option<f32> {
+    let direction = to - from;
+    let mut enter: f32 = 0.;
+    let mut exit: f32 = 1.;
+    for &(from, direction, min, max) in &[
+        (from.x, direction.x, min.x, max.x),
+        (from.y, direction.y, min.y, max.y),
+    ] {
+        if direction == 0. {
+            if from < min || from > max {
+                return None;
+            }
+        } else {
+            let a = (min - from) / direction;
+            let b = (max - from) / direction;
+            enter = enter.max(a.min(b));
+            exit = exit.min(a.max(b));
+            if enter > exit {
+                return None;
+            }
+        }
+    }
+    Some(enter)
+}
+
+fn hit_segment(start: Vec2, end: Vec2, from: Vec2, to: Vec2) -> Option<f32> {
+    let direction = to - from;
+    let segment = end - start;
+    let denominator = cross(direction, segment);
+    if denominator == 0. {
+        return None;
+    }
+    let t = cross(start - from, segment) / denominator;
+    let u = cross(start - from, direction) / denominator;
+    if (0.0..=1.).contains(&t) && (0.0..=1.).contains(&u) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+fn cross(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roof() -> Collider {
+        Collider::Box {
+            half_size: Vec2::new(5., 5.),
+        }
+    }
+
+    #[test]
+    fn the_ground_is_hit_from_its_height() {
+        let ground = Collider::Ground;
+        let origin = Vec2::new(0., -10.);
+        assert_eq!(
+            ground.hit(origin, Vec2::new(3., 10.), Vec2::new(3., -30.)),
+            Some(0.5)
+        );
+        assert_eq!(
+            ground.hit(origin, Vec2::new(3., -10.), Vec2::new(3., -20.)),
+            Some(0.)
+        );
+        // Ending exactly on the ground is only a hit once the next step goes on from there.
+        assert_eq!(
+            ground.hit(origin, Vec2::new(3., 0.), Vec2::new(3., -10.)),
+            None
+        );
+        assert_eq!(
+            ground.hit(origin, Vec2::new(3., -20.), Vec2::new(3., -30.)),
+            None
+        );
+    }
+
+    #[test]
+    fn a_box_is_hit_where_the_path_enters_it() {
+        let origin = Vec2::new(0., 0.);
+        assert_eq!(
+            roof().hit(origin, Vec2::new(0., 10.), Vec2::new(0., -10.)),
+            Some(0.25)
+        );
+        assert_eq!(
+            roof().hit(origin, Vec2::new(-10., 4.), Vec2::new(10., 4.)),
+            Some(0.25)
+        );
+        assert_eq!(
+            roof().hit(origin, Vec2::new(0., 20.), Vec2::new(0., 10.)),
+            None
+        );
+    }
+
+    #[test]
+    fn a_path_starting_inside_of_a_box_hits_it_right_away() {
+        assert_eq!(
+            roof().hit(Vec2::zero(), Vec2::new(1., 1.), Vec2::new(30., -30.)),
+            Some(0.)
+        );
+    }
+
+    #[test]
+    fn a_path_parallel_to_the_sides_of_a_box_only_hits_it_within_them() {
+        let origin = Vec2::zero();
+        assert_eq!(
+            roof().hit(origin, Vec2::new(6., 10.), Vec2::new(6., -10.)),
+            None
+        );
+        assert_eq!(
+            roof().hit(origin, Vec2::new(5., 10.), Vec2::new(5., -10.)),
+            Some(0.25)
+        );
+        assert_eq!(
+            roof().hit(origin, Vec2::new(-10., 6.), Vec2::new(10., 6.)),
+            None
+        );
+    }
+
+    #[test]
+    fn a_segment_is_hit_where_the_path_crosses_it() {
+        let umbrella = Collider::Segment {
+            start: Vec2::new(-10., 0.),
+            end: Vec2::new(10., 0.),
+        };
+        let origin = Vec2::new(100., 50.);
+        assert_eq!(
+            umbrella.hit(origin, Vec2::new(95., 60.), Vec2::new(95., 20.)),
+            Some(0.25)
+        );
+        assert_eq!(
+            umbrella.hit(origin, Vec2::new(120., 60.), Vec2::new(120., 20.)),
+            None
+        );
+        assert_eq!(
+            umbrella.hit(origin, Vec2::new(95., 80.), Vec2::new(95., 60.)),
+            None
+        );
+    }
+
+    #[test]
+    fn a_segment_parallel_to_the_path_is_never_hit() {
+        let wall = Collider::Segment {
+            start: Vec2::new(0., -10.),
+            end: Vec2::new(0., 10.),
+        };
+        assert_eq!(
+            wall.hit(Vec2::zero(), Vec2::new(5., 20.), Vec2::new(5., -20.)),
+            None
+        );
+        assert_eq!(
+            wall.hit(Vec2::zero(), Vec2::new(0., 20.), Vec2::new(0., -20.)),
+            None
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use crate::{
+    layer, sky, DayNightConfig, DepthLayer, GradientError, LightningConfig, SkyGradient,
+    SplashConfig, WeatherConfig, Wind,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt, fs, io, ops::Range, path::Path};
+
+/// How hard it rains, in drops per second per 100×100 pixels of the viewport.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Intensity {
+    Drizzle,
+    Shower,
+    Downpour,
+    /// Any other number of drops per second per 100×100 pixels.
+    Density(f32),
+}
+
+impl Intensity {
+    /// Drops per second per 100×100 pixels.
+    pub fn density(&self) -> f32 {
+        match self {
+            Intensity::Drizzle => 0.5,
+            Intensity::Shower => 3.,
+            Intensity::Downpour => 10.,
+            Intensity::Density(density) => *density,
+        }
+    }
+
+    /// How many drops per second fall on an area of `width` by `height` pixels.
+    pub fn drops_per_second(&self, width: f32, height: f32) -> f32 {
+        self.density() * width.max(0.) * height.max(0.) / (100. * 100.)
+    }
+}
+
+/// How the drops are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderMode {
+    /// Every drop is a sprite of its own.
+    Sprites,
+    /// All drops of an area are drawn at once as a single mesh, rebuilt every frame.
+    Batched,
+}
+
+/// The parameters that define how the rain looks and behaves.
+///
+/// It can be loaded from a RON file in which every field is optional.
+///
+/// Every `RainArea` has its own, but the timestep, seed, wind, weather, lightning, sky and
+/// day/night cycle are shared by all areas and only read from the one given to the `RainPlugin`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RainConfig {
+    /// Seconds the simulation moves forward in each step.
+    /// This is only read at the start.
+    pub timestep: f32,
+    pub intensity: Intensity,
+    /// The most drops there can be at once, including the ones waiting in the `DropPool`.
+    pub max_drops: usize,
+    pub drop_length: Range<f32>,
+    pub drop_width: f32,
+    #[serde(with = "rgba")]
+    pub drop_color: Color,
+    /// The downwards speed in pixels per second that drops start out with.
+    pub initial_speed: f32,
+    /// The downwards acceleration in pixels per second squared.
+    pub gravity: f32,
+    /// The highest falling speed a drop can reach, from the shortest to the longest drop.
+    pub terminal_velocity: Range<f32>,
+    /// What the random number generator is seeded with.
+    /// The same seed results in the same rain if the simulation is stepped the same way.
+    /// A random seed is used if this is `None`.
+    pub seed: Option<u64>,
+    /// The wind at the start. It can be changed afterwards through the `Wind` resource.
+    pub wind: Wind,
+    pub splash: SplashConfig,
+    pub weather: WeatherConfig,
+    pub lightning: LightningConfig,
+    /// This is only read at the start.
+    pub render_mode: RenderMode,
+    /// The background gradient at the start.
+    /// It can be changed afterwards through the `SkyGradient` resource.
+    pub sky: SkyGradient,
+    /// Darkens the sky and the drops towards the night and back over the day.
+    pub day_night: DayNightConfig,
+    /// The layers of rain from the farthest to the nearest.
+    /// Every drop falls in one of them, picked at random.
+    pub layers: Vec<DepthLayer>,
+    /// How far outside of the viewport drops spawn, in pixels.
+    /// Drops left farther outside than this when the camera moves are recycled.
+    pub margin: f32,
+}
+
+impl Default for RainConfig {
+    fn default() -> Self {
+        Self {
+            timestep: 1. / 120.,
+            intensity: Intensity::Shower,
+            max_drops: 10000,
+            drop_length: 25.0..75.,
+            drop_width: 2.,
+            drop_color: Color::rgb(0.3, 0.3, 0.75),
+            initial_speed: 1500.,
+            gravity: 2000.,
+            terminal_velocity: 2000.0..3500.,
+            seed: None,
+            wind: Wind::default(),
+            splash: SplashConfig::default(),
+            weather: WeatherConfig::default(),
+            lightning: LightningConfig::default(),
+            render_mode: RenderMode::Sprites,
+            sky: SkyGradient::default(),
+            day_night: DayNightConfig::default(),
+            layers: layer::default_layers(),
+            margin: 50.,
+        }
+    }
+}
+
+impl RainConfig {
+    /// Reads and validates the configuration in the RON file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    /// Parses and validates a configuration written in RON.
+    pub fn from_ron(ron: &str) -> Result<Self, ConfigError> {
+        let config: Self = ron::de::from_str(ron)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// How long a drop of `length` is compared to the others,
+    /// from 0 for the shortest to 1 for the longest.
+    pub fn drop_size(&self, length: f32) -> f32 {
+        (length - self.drop_length.start) / (self.drop_length.end - self.drop_length.start)
+    }
+
+    /// The layer at `index`, or the nearest one if there are fewer layers.
+    pub fn layer(&self, index: usize) -> DepthLayer {
+        self.layers
+            .get(index)
+            .or_else(|| self.layers.last())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Makes sure every value is one the rain can work with.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        positive("timestep", self.timestep)?;
+        let density = self.intensity.density();
+        if !density.is_finite() {
+            return Err(ConfigError::invalid("intensity", "must be a finite number"));
+        }
+        not_negative("intensity", density)?;
+        if self.max_drops == 0 {
+            return Err(ConfigError::invalid("max_drops", "must be greater than 0"));
+        }
+        range("drop_length", &self.drop_length)?;
+        positive("drop_width", self.drop_width)?;
+        color("drop_color", self.drop_color)?;
+        not_negative("initial_speed", self.initial_speed)?;
+        not_negative("gravity", self.gravity)?;
+        range("terminal_velocity", &self.terminal_velocity)?;
+        if !(-1.0..=1.).contains(&self.wind.direction) {
+            return Err(ConfigError::invalid(
+                "wind.direction",
+                "must be between -1 and 1",
+            ));
+        }
+        not_negative("wind.strength", self.wind.strength)?;
+        not_negative("wind.gustiness", self.wind.gustiness)?;
+        not_negative("wind.gust_frequency", self.wind.gust_frequency)?;
+        range("splash.size", &self.splash.size)?;
+        not_negative("splash.speed", self.splash.speed)?;
+        positive("splash.lifetime", self.splash.lifetime)?;
+        for (i, step) in self.weather.schedule.iter().enumerate() {
+            not_negative(&format!("weather.schedule[{}].after", i), step.after)?;
+            not_negative(
+                &format!("weather.schedule[{}].transition", i),
+                step.transition,
+            )?;
+        }
+        not_negative("lightning.frequency", self.lightning.frequency)?;
+        positive("lightning.decay", self.lightning.decay)?;
+        gradient("sky", &self.sky)?;
+        positive("day_night.cycle", self.day_night.cycle)?;
+        fraction("day_night.start", self.day_night.start)?;
+        gradient("day_night.night_sky", &self.day_night.night_sky)?;
+        fraction(
+            "day_night.night_brightness",
+            self.day_night.night_brightness,
+        )?;
+        fraction("day_night.night_alpha", self.day_night.night_alpha)?;
+        not_negative("margin", self.margin)?;
+        if self.layers.is_empty() {
+            return Err(ConfigError::invalid(
+                "layers",
+                "must have at least one layer",
+            ));
+        }
+        for (i, layer) in self.layers.iter().enumerate() {
+            positive(&format!("layers[{}].size", i), layer.size)?;
+            positive(&format!("layers[{}].speed", i), layer.speed)?;
+            fraction(&format!("layers[{}].alpha", i), layer.alpha)?;
+        }
+        Ok(())
+    }
+}
+
+fn positive(field: &str, value: f32) -> Result<(), ConfigError> {
+    if value > 0. {
+        Ok(())
+    } else {
+        Err(ConfigError::invalid(field, "must be greater than 0"))
+    }
+}
+
+fn not_negative(field: &str, value: f32) -> Result<(), ConfigError> {
+    if value >= 0. {
+        Ok(())
+    } else {
+        Err(ConfigError::invalid(field, "must not be negative"))
+    }
+}
+
+fn fraction(field: &str, value: f32) -> Result<(), ConfigError> {
+    if (0.0..=1.).contains(&value) {
+        Ok(())
+    } else {
+        Err(ConfigError::invalid(field, "must be between 0 and 1"))
+    }
+}
+
+fn range(field: &str, range: &Range<f32>) -> Result<(), ConfigError> {
+    positive(field, range.start)?;
+    if range.start < range.end {
+        Ok(())
+    } else {
+        Err(ConfigError::invalid(field, "start must be less than end"))
+    }
+}
+
+fn color(field: &str, color: Color) -> Result<(), ConfigError> {
+    let components = [color.r(), color.g(), color.b(), color.a()];
+    if components.iter().all(|c| (0.0..=1.).contains(c)) {
+        Ok(())
+    } else {
+        Err(ConfigError::invalid(
+            field,
+            "every component must be between 0 and 1",
+        ))
+    }
+}
+
+fn gradient(field: &str, gradient: &SkyGradient) -> Result<(), ConfigError> {
+    match sky::check_stops(gradient.stops()) {
+        Ok(()) => Ok(()),
+        Err(GradientError::Count) => {
+            Err(ConfigError::invalid(field, "must have from 1 to 4 colours"))
+        }
+        Err(GradientError::Position(i)) => Err(ConfigError::invalid(
+            &format!("{}[{}].position", field, i),
+            "must be between the previous position and 1",
+        )),
+        Err(GradientError::Color(i)) => Err(ConfigError::invalid(
+            &format!("{}[{}].color", field, i),
+            "every component must be between 0 and 1",
+        )),
+    }
+}
+
+/// Colours are written as `(red, green, blue, alpha)` with components from 0 to 1.
+pub(crate) mod rgba {
+    use bevy::prelude::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        (color.r(), color.g(), color.b(), color.a()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let (r, g, b, a) = Deserialize::deserialize(deserializer)?;
+        Ok(Color::rgba(r, g, b, a))
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(ron::Error),
+    Invalid { field: String, reason: &'static str },
+}
+
+impl ConfigError {
+    fn invalid(field: &str, reason: &'static str) -> Self {
+        ConfigError::Invalid {
+            field: field.to_string(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "could not read the configuration: {}", err),
+            ConfigError::Parse(err) => write!(f, "could not parse the configuration: {}", err),
+            ConfigError::Invalid { field, reason } => {
+                write!(f, "invalid value for `{}`: {}", field, reason)
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            ConfigError::Parse(err) => Some(err),
+            ConfigError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<ron::Error> for ConfigError {
+    fn from(err: ron::Error) -> Self {
+        ConfigError::Parse(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The field `ron` is rejected for, failing if it is accepted or rejected for another reason.
+    fn invalid_field(ron: &str) -> String {
+        match RainConfig::from_ron(ron) {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            Err(err) => panic!("`{}` failed with: {}", ron, err),
+            Ok(_) => panic!("`{}` was accepted", ron),
+        }
+    }
+
+    #[test]
+    fn nested_fields_are_named_with_their_parent() {
+        assert_eq!(invalid_field("(wind: (direction: 2.0))"), "wind.direction");
+        assert_eq!(
+            invalid_field("(day_night: (night_alpha: -0.5))"),
+            "day_night.night_alpha"
+        );
+    }
+
+    #[test]
+    fn indexed_fields_are_named_with_their_index() {
+        assert_eq!(
+            invalid_field(
+                "(layers: [
+                    (size: 1.0, speed: 1.0, alpha: 1.0),
+                    (size: 1.0, speed: 1.0, alpha: 2.0),
+                ])"
+            ),
+            "layers[1].alpha"
+        );
+        assert_eq!(
+            invalid_field(
+                "(sky: [
+                    (position: 0.0, color: (0.0, 0.0, 0.0, 1.0)),
+                    (position: 0.8, color: (0.5, 0.5, 0.5, 1.0)),
+                    (position: 0.4, color: (1.0, 1.0, 1.0, 1.0)),
+                ])"
+            ),
+            "sky[2].position"
+        );
+        assert_eq!(
+            invalid_field(
+                "(weather: (schedule: [(after: -1.0, weather: Storm, transition: 0.0)]))"
+            ),
+            "weather.schedule[0].after"
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        match RainConfig::from_ron("(drop_colour: (1.0, 1.0, 1.0, 1.0))") {
+            Err(err @ ConfigError::Parse(_)) => assert!(err.to_string().contains("drop_colour")),
+            Err(err) => panic!("failed with: {}", err),
+            Ok(_) => panic!("an unknown field was accepted"),
+        }
+        match RainConfig::from_ron("(wind: (speed: 1.0))") {
+            Err(err @ ConfigError::Parse(_)) => assert!(err.to_string().contains("speed")),
+            Err(err) => panic!("failed with: {}", err),
+            Ok(_) => panic!("an unknown nested field was accepted"),
+        }
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_the_defaults() {
+        let config = RainConfig::from_ron("(intensity: Downpour, wind: (strength: 10.0))").unwrap();
+        let defaults = RainConfig::default();
+        assert_eq!(config.intensity, Intensity::Downpour);
+        assert_eq!(config.wind.strength, 10.);
+        assert_eq!(config.wind.direction, defaults.wind.direction);
+        assert_eq!(config.wind.gustiness, defaults.wind.gustiness);
+        assert_eq!(config.max_drops, defaults.max_drops);
+        assert_eq!(config.drop_length, defaults.drop_length);
+        assert_eq!(config.layers, defaults.layers);
+        assert_eq!(config.sky, defaults.sky);
+        assert_eq!(config.render_mode, defaults.render_mode);
+    }
+
+    #[test]
+    fn an_empty_file_is_the_default_configuration() {
+        let config = RainConfig::from_ron("()").unwrap();
+        assert_eq!(config.timestep, RainConfig::default().timestep);
+        assert!(RainConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn intensities_are_finite_and_not_negative() {
+        for &density in &[-1., f32::NAN, f32::INFINITY] {
+            let config = RainConfig {
+                intensity: Intensity::Density(density),
+                ..Default::default()
+            };
+            match config.validate() {
+                Err(ConfigError::Invalid { field, .. }) => assert_eq!(field, "intensity"),
+                _ => panic!("a density of {} was accepted", density),
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{RainConfig, SkyGradient};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// How the time of day changes the sky and the rain.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DayNightConfig {
+    /// Whether the time of day changes the sky and the drops at all.
+    pub enabled: bool,
+    /// Seconds a whole day takes.
+    pub cycle: f32,
+    /// The time of day at the start,
+    /// from 0 at midnight over 0.5 at noon to 1 at the next midnight.
+    pub start: f32,
+    /// Whether the clock stands still.
+    pub frozen: bool,
+    /// The background at midnight. At noon it is the configured `sky`.
+    pub night_sky: SkyGradient,
+    /// How bright drops are at midnight compared to noon.
+    pub night_brightness: f32,
+    /// How opaque drops are at midnight compared to noon.
+    pub night_alpha: f32,
+}
+
+impl Default for DayNightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cycle: 240.,
+            start: 0.5,
+            frozen: false,
+            night_sky: SkyGradient::new(Color::rgb(0.02, 0.04, 0.1), Color::rgb(0.1, 0.12, 0.25)),
+            night_brightness: 0.4,
+            night_alpha: 0.5,
+        }
+    }
+}
+
+/// The time of day.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    time_of_day: f32,
+    /// Seconds a whole day takes.
+    pub cycle: f32,
+    /// Whether the clock stands still.
+    pub frozen: bool,
+}
+
+impl Clock {
+    pub fn new(config: &DayNightConfig) -> Self {
+        Self {
+            time_of_day: config.start.rem_euclid(1.),
+            cycle: config.cycle,
+            frozen: config.frozen,
+        }
+    }
+
+    /// From 0 at midnight over 0.5 at noon to 1 at the next midnight.
+    pub fn time_of_day(&self) -> f32 {
+        self.time_of_day
+    }
+
+    pub fn set_time_of_day(&mut self, time_of_day: f32) {
+        self.time_of_day = time_of_day.rem_euclid(1.);
+    }
+
+    /// How light it is, from 0 at midnight to 1 at noon.
+    pub fn daylight(&self) -> f32 {
+        0.5 - 0.5 * (self.time_of_day * 2. * PI).cos()
+    }
+
+    /// `color` darkened and made more translucent depending on the time of day.
+    pub fn tint(&self, color: Color, config: &DayNightConfig) -> Color {
+        if !config.enabled {
+            return color;
+        }
+        let daylight = self.daylight();
+        let brightness = config.night_brightness + (1. - config.night_brightness) * daylight;
+        let alpha = config.night_alpha + (1. - config.night_alpha) * daylight;
+        Color::rgba(
+            color.r() * brightness,
+            color.g() * brightness,
+            color.b() * brightness,
+            color.a() * alpha,
+        )
+    }
+}
+
+pub(crate) fn tick_clock(mut clock: ResMut<Clock>, time: Res<Time>) {
+    if !clock.frozen && clock.cycle > 0. {
+        let time_of_day = clock.time_of_day + time.delta_seconds() / clock.cycle;
+        clock.set_time_of_day(time_of_day);
+    }
+}
+
+/// Blends the sky between the night and the configured `sky` by the time of day,
+/// and puts the configured `sky` back once day and night are disabled.
+pub(crate) fn light_sky(
+    mut was_enabled: Local<bool>,
+    clock: Res<Clock>,
+    mut sky: ResMut<SkyGradient>,
+    config: Res<RainConfig>,
+) {
+    if !config.day_night.enabled {
+        if *was_enabled {
+            *was_enabled = false;
+            *sky = config.sky.clone();
+        }
+        return;
+    }
+    *was_enabled = true;
+    let lit = config
+        .day_night
+        .night_sky
+        .lerp(&config.sky, clock.daylight());
+    if *sky != lit {
+        *sky = lit;
+    }
+}
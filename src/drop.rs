@@ -0,0 +1,246 @@
+use crate::{
+    area::InArea, layer, Collider, DropImpact, DropMaterials, DropPool, Position, RainArea,
+    RainConfig, RenderMode, Viewport, Weather, Wind,
+};
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::Rng;
+
+/// A single raindrop.
+pub struct Drop {
+    pub length: f32,
+    /// How long this drop is compared to the others before the weather and its layer scale it,
+    /// from 0 for the shortest to 1 for the longest. Larger drops are shaded brighter.
+    pub size: f32,
+    /// The highest falling speed of this drop. Longer drops fall faster.
+    pub terminal_velocity: f32,
+    /// Which of the `RainConfig::layers` this drop falls in.
+    pub layer: usize,
+    /// The entity with the `RainArea` this drop falls in.
+    pub area: Entity,
+    pub(crate) active: bool,
+}
+
+impl Drop {
+    /// Whether this drop is falling or waiting in the `DropPool` to be used again.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+/// How many pixels per second an entity moves.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Velocity(pub Vec2);
+
+pub(crate) fn spawn_drop(
+    commands: &mut Commands,
+    mut areas: Query<(Entity, &mut RainArea, &RainConfig, &Viewport, &mut DropPool)>,
+    mut drops: Query<(&mut Drop, &mut Velocity, &mut Position, &mut Transform)>,
+    mut rng: ResMut<SmallRng>,
+    wind: Res<Wind>,
+    weather: Res<Weather>,
+    config: Res<RainConfig>,
+) {
+    let conditions = weather.conditions();
+    for (area_entity, mut area, area_config, viewport, mut pool) in areas.iter_mut() {
+        if area.is_paused() {
+            continue;
+        }
+        let margin = area_config.margin;
+        area.accumulator += area_config
+            .intensity
+            .drops_per_second(viewport.width + 2. * margin, viewport.height)
+            * conditions.intensity
+            * config.timestep;
+        // Drops beyond `max_drops` could not fall anyway, so they are not owed later either.
+        let room = area_config.max_drops.saturating_sub(pool.active());
+        area.accumulator = area.accumulator.min(room as f32);
+        let spawns = area.accumulator.floor();
+        area.accumulator -= spawns;
+
+        for _ in 0..spawns as usize {
+            let x = rng.gen_range(viewport.left() - margin..viewport.right() + margin);
+            let layer_index = rng.gen_range(0..area_config.layers.len().max(1));
+            let layer = area_config.layer(layer_index);
+            let length = rng.gen_range(area_config.drop_length.clone());
+            let size = area_config.drop_size(length);
+            let terminal_velocity = (area_config.terminal_velocity.start
+                + (area_config.terminal_velocity.end - area_config.terminal_velocity.start) * size)
+                * layer.speed;
+            let length = length * conditions.drop_length * layer.size;
+            let velocity = Vec2::new(wind.speed(), -area_config.initial_speed) * layer.speed;
+
+            let new_drop = Drop {
+                length,
+                size,
+                terminal_velocity,
+                layer: layer_index,
+                area: area_entity,
+                active: true,
+            };
+            let new_position = Position::new(Vec2::new(x, viewport.top() + margin));
+
+            // A drop that is gone from the pool is forgotten and replaced by a new one.
+            let pooled = match pool.take() {
+                Some(entity) => drops.get_mut(entity).ok(),
+                None => None,
+            };
+            if let Some((mut drop, mut drop_velocity, mut position, mut transform)) = pooled {
+                *drop = new_drop;
+                *drop_velocity = Velocity(velocity);
+                *position = new_position;
+                transform.translation.z = layer::depth(layer_index);
+                pool.add();
+            } else if pool.len() < area_config.max_drops {
+                commands
+                    .spawn((
+                        Transform {
+                            translation: new_position.current.extend(layer::depth(layer_index)),
+                            rotation: Wind::tilt(velocity),
+                            ..Default::default()
+                        },
+                        GlobalTransform::default(),
+                    ))
+                    .with(new_drop)
+                    .with(Velocity(velocity))
+                    .with(new_position);
+                pool.add();
+            }
+        }
+    }
+}
+
+/// Gives newly spawned drops a sprite so they can be seen.
+pub(crate) fn add_drop_sprites(
+    commands: &mut Commands,
+    drops: Query<(Entity, &Drop, &Transform), Added<Drop>>,
+    areas: Query<(&RainConfig, &DropMaterials)>,
+) {
+    for (entity, drop, transform) in drops.iter() {
+        let (config, palette) = match areas.get(drop.area) {
+            Ok(area) => area,
+            Err(_) => continue,
+        };
+        if config.render_mode != RenderMode::Sprites {
+            continue;
+        }
+        commands.insert(
+            entity,
+            SpriteBundle {
+                material: palette.shade(drop.layer, drop.size),
+                sprite: Sprite::new(Vec2::new(
+                    config.drop_width * config.layer(drop.layer).size,
+                    drop.length,
+                )),
+                transform: *transform,
+                global_transform: GlobalTransform::from(*transform),
+                ..Default::default()
+            },
+        );
+        commands.insert_one(entity, InArea(drop.area));
+    }
+}
+
+/// Shows drops that are in use and hides the ones in the pool.
+pub(crate) fn update_drop_sprites(
+    mut drops: Query<(&Drop, &mut Sprite, &mut Handle<ColorMaterial>, &mut Visible), Changed<Drop>>,
+    areas: Query<(&RainConfig, &DropMaterials)>,
+) {
+    for (drop, mut sprite, mut material, mut visible) in drops.iter_mut() {
+        let (config, palette) = match areas.get(drop.area) {
+            Ok(area) => area,
+            Err(_) => continue,
+        };
+        sprite.size = Vec2::new(
+            config.drop_width * config.layer(drop.layer).size,
+            drop.length,
+        );
+        *material = palette.shade(drop.layer, drop.size);
+        visible.is_visible = drop.active;
+    }
+}
+
+/// Retires drops that hit the bottom of the viewport
+/// and quietly recycles the ones the camera left behind.
+pub(crate) fn despawn_drops(
+    mut drops: Query<(Entity, &mut Drop, &Velocity, &Position)>,
+    mut areas: Query<(&RainArea, &RainConfig, &Viewport, &mut DropPool)>,
+    mut impacts: EventWriter<DropImpact>,
+) {
+    for (entity, mut drop, velocity, position) in drops.iter_mut() {
+        if !drop.active {
+            continue;
+        }
+        let (area, config, viewport, mut pool) = match areas.get_mut(drop.area) {
+            Ok(area) => area,
+            Err(_) => continue,
+        };
+        if area.is_paused() {
+            continue;
+        }
+        if position.current.y < viewport.bottom() {
+            pool.retire(entity, &mut drop);
+            impacts.send(DropImpact {
+                position: Vec2::new(position.current.x, viewport.bottom()),
+                velocity: velocity.0,
+                collider: None,
+                area: drop.area,
+                layer: drop.layer,
+            });
+        } else if !viewport.contains(position.current, config.margin) {
+            pool.retire(entity, &mut drop);
+        }
+    }
+}
+
+pub(crate) fn make_drops_drop(
+    mut drops: Query<(Entity, &mut Drop, &mut Velocity, &mut Position)>,
+    mut areas: Query<(&RainArea, &RainConfig, &mut DropPool)>,
+    colliders: Query<(Entity, &Collider, &GlobalTransform)>,
+    wind: Res<Wind>,
+    config: Res<RainConfig>,
+    mut impacts: EventWriter<DropImpact>,
+) {
+    let delta = config.timestep;
+    for (entity, mut drop, mut velocity, mut position) in drops.iter_mut() {
+        if !drop.active {
+            continue;
+        }
+        let (area, area_config, mut pool) = match areas.get_mut(drop.area) {
+            Ok(area) => area,
+            Err(_) => continue,
+        };
+        if area.is_paused() {
+            continue;
+        }
+
+        let layer = area_config.layer(drop.layer);
+        velocity.0.x = wind.speed() * layer.speed;
+        velocity.0.y =
+            (velocity.0.y - area_config.gravity * layer.speed * delta).max(-drop.terminal_velocity);
+
+        let from = position.current;
+        let to = from + velocity.0 * delta;
+        let hit = colliders
+            .iter()
+            .filter_map(|(collider_entity, collider, collider_transform)| {
+                collider
+                    .hit(collider_transform.translation.truncate(), from, to)
+                    .map(|t| (t, collider_entity))
+            })
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+
+        if let Some((t, collider)) = hit {
+            pool.retire(entity, &mut drop);
+            impacts.send(DropImpact {
+                position: from + (to - from) * t,
+                velocity: velocity.0,
+                collider: Some(collider),
+                area: drop.area,
+                layer: drop.layer,
+            });
+        } else {
+            position.move_to(to);
+        }
+    }
+}
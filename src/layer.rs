@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/// One of the layers of rain at different distances from the camera.
+///
+/// Each value scales the one of a drop in the nearest layer,
+/// so farther layers have lower values.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DepthLayer {
+    /// How long and wide drops are.
+    pub size: f32,
+    /// How fast drops fall and are blown aside.
+    pub speed: f32,
+    /// How opaque drops are.
+    pub alpha: f32,
+}
+
+impl DepthLayer {
+    pub fn new(size: f32, speed: f32, alpha: f32) -> Self {
+        Self { size, speed, alpha }
+    }
+}
+
+impl Default for DepthLayer {
+    fn default() -> Self {
+        Self::new(1., 1., 1.)
+    }
+}
+
+/// Three layers, from the farthest to the nearest.
+pub(crate) fn default_layers() -> Vec<DepthLayer> {
+    vec![
+        DepthLayer::new(0.4, 0.5, 0.35),
+        DepthLayer::new(0.7, 0.75, 0.65),
+        DepthLayer::new(1., 1., 1.),
+    ]
+}
+
+/// How far in front of the background the drops in the layer at `index` are drawn.
+pub(crate) fn depth(index: usize) -> f32 {
+    1. + index as f32
+}
@@ -0,0 +1,180 @@
+mod area;
+mod background;
+mod batch;
+mod collision;
+mod config;
+mod day_night;
+mod drop;
+mod layer;
+mod lightning;
+mod palette;
+mod pool;
+mod reload;
+mod simulation;
+mod sky;
+mod splash;
+mod viewport;
+mod weather;
+mod wind;
+
+pub use area::{RainArea, RainAreaBundle};
+pub use background::{Background, Uniforms};
+pub use batch::{BatchBuffer, DropQuad};
+pub use collision::Collider;
+pub use config::{ConfigError, Intensity, RainConfig, RenderMode};
+pub use day_night::{Clock, DayNightConfig};
+pub use drop::{Drop, Velocity};
+pub use layer::DepthLayer;
+pub use lightning::{bolt_path, Lightning, LightningConfig, LightningStrike};
+pub use palette::DropMaterials;
+pub use pool::{DropPool, DropPoolDiagnosticsPlugin};
+pub use reload::ConfigChanged;
+pub use simulation::{Position, SIMULATION_STAGE, TIMESTEP};
+pub use sky::{GradientError, GradientStop, SkyGradient};
+pub use splash::{DropImpact, Splash, SplashConfig};
+pub use viewport::Viewport;
+pub use weather::{Conditions, Weather, WeatherConfig, WeatherEvent, WeatherKind, WeatherStep};
+pub use wind::Wind;
+
+use bevy::{core::FixedTimestep, prelude::*};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use std::{path::PathBuf, sync::Arc};
+
+/// Adds rain and its gradient background to an app.
+///
+/// The plugin makes it rain in the primary window, or in the headless viewport.
+/// Other windows get rain of their own by spawning a `RainAreaBundle` for them.
+pub struct RainPlugin {
+    config: RainConfig,
+    headless: Option<Viewport>,
+    config_path: Option<PathBuf>,
+    overrides: Option<reload::Overrides>,
+}
+
+impl RainPlugin {
+    pub fn new(config: RainConfig) -> Self {
+        Self {
+            config,
+            headless: None,
+            config_path: None,
+            overrides: None,
+        }
+    }
+
+    /// Simulates the rain inside of `viewport` without a window or anything being rendered.
+    ///
+    /// This only needs the `MinimalPlugins`
+    /// so the drops can be stepped through in tests and batch jobs.
+    pub fn headless(config: RainConfig, viewport: Viewport) -> Self {
+        Self {
+            config,
+            headless: Some(viewport),
+            config_path: None,
+            overrides: None,
+        }
+    }
+
+    /// Watches the configuration file at `path` and applies any changes to it while running.
+    ///
+    /// Existing drops are kept. If the file is invalid, the previous configuration stays in use.
+    pub fn watch(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Applies `overrides` to the configuration, and again to every configuration reloaded
+    /// from the watched file, so settings made elsewhere like on the command line stay in use.
+    pub fn with_overrides(
+        mut self,
+        overrides: impl Fn(&mut RainConfig) + Send + Sync + 'static,
+    ) -> Self {
+        overrides(&mut self.config);
+        self.overrides = Some(Arc::new(overrides));
+        self
+    }
+}
+
+impl Default for RainPlugin {
+    fn default() -> Self {
+        Self::new(RainConfig::default())
+    }
+}
+
+impl Plugin for RainPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let seed = self.config.seed.unwrap_or_else(rand::random);
+        info!("rain seed: {}", seed);
+
+        app.insert_resource(SmallRng::seed_from_u64(seed))
+            .insert_resource(self.config.wind.clone())
+            .insert_resource(Weather::new(&self.config.weather))
+            .insert_resource(Lightning::default())
+            .insert_resource(self.config.sky.clone())
+            .insert_resource(Clock::new(&self.config.day_night))
+            .insert_resource(self.config.clone())
+            // Headless apps have no `WindowPlugin`, and no windows for their areas.
+            .init_resource::<Windows>()
+            .add_stage_after(
+                stage::PRE_UPDATE,
+                SIMULATION_STAGE,
+                SystemStage::serial().with_run_criteria(
+                    FixedTimestep::step(self.config.timestep as f64).with_label(TIMESTEP),
+                ),
+            )
+            // The weather and wind set the conditions the drops spawn and fall in,
+            // and the drops that landed splash in the same step.
+            .add_system_to_stage(SIMULATION_STAGE, weather::update_weather.system())
+            .add_system_to_stage(SIMULATION_STAGE, wind::blow_wind.system())
+            .add_system_to_stage(SIMULATION_STAGE, drop::spawn_drop.system())
+            .add_system_to_stage(SIMULATION_STAGE, drop::make_drops_drop.system())
+            .add_system_to_stage(SIMULATION_STAGE, drop::despawn_drops.system())
+            .add_system_to_stage(SIMULATION_STAGE, splash::splash.system())
+            .add_system_to_stage(SIMULATION_STAGE, splash::move_splashes.system())
+            .add_system_to_stage(SIMULATION_STAGE, lightning::strike_lightning.system())
+            .add_system_to_stage(stage::PRE_UPDATE, area::watch_windows.system())
+            .add_system(simulation::interpolate.system())
+            .add_system(day_night::tick_clock.system())
+            .add_event::<DropImpact>()
+            .add_event::<WeatherEvent>()
+            .add_event::<LightningStrike>()
+            .add_event::<ConfigChanged>();
+
+        if let Some(path) = &self.config_path {
+            app.insert_resource(reload::ConfigWatcher::new(
+                path.clone(),
+                self.overrides.clone(),
+            ))
+            .add_system(reload::watch_config.system());
+        }
+
+        let main_area = RainAreaBundle::main(self.config.clone(), self.headless);
+        let main_area = app.app.world.spawn(main_area);
+        app.app.world.insert_one(main_area, area::MainArea).unwrap();
+
+        if self.headless.is_none() {
+            app.add_asset::<Uniforms>()
+                .add_startup_system(background::setup.system())
+                .add_startup_system(batch::setup.system())
+                .add_system_to_stage(stage::PRE_UPDATE, area::setup_areas.system())
+                .add_system_to_stage(stage::PRE_UPDATE, viewport::follow_camera.system())
+                .add_system(area::close_areas.system())
+                .add_system_to_stage(
+                    bevy::render::stage::RENDER_RESOURCE,
+                    area::separate_areas.system(),
+                )
+                .add_system(splash::add_splash_sprites.system())
+                .add_system(splash::fade_splashes.system())
+                .add_system(background::update_background.system())
+                .add_system(sky::paint_sky.system())
+                .add_system(lightning::flash_background.system())
+                .add_system(lightning::draw_bolts.system())
+                .add_system(lightning::fade_bolts.system())
+                .add_system(day_night::light_sky.system())
+                .add_system(palette::tint_drops.system())
+                .add_system(drop::add_drop_sprites.system())
+                .add_system(drop::update_drop_sprites.system())
+                .add_system(batch::update_batch.system());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{area::InArea, Background, RainConfig, Uniforms, Viewport, Weather, WeatherKind};
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How lightning strikes during storms.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LightningConfig {
+    /// About how many times per second lightning strikes while there is a storm.
+    pub frequency: f32,
+    /// Seconds until a flash has mostly faded out.
+    pub decay: f32,
+    /// Whether to draw the bolts and not only flash the sky.
+    pub bolts: bool,
+}
+
+impl Default for LightningConfig {
+    fn default() -> Self {
+        Self {
+            frequency: 0.1,
+            decay: 0.4,
+            bolts: true,
+        }
+    }
+}
+
+/// Sent to every area when lightning strikes.
+#[derive(Clone, Debug)]
+pub struct LightningStrike {
+    /// How bright the flash is, from 0 to 1.
+    pub intensity: f32,
+    /// The points of the bolt from the top of the viewport to the ground.
+    pub bolt: Vec<Vec2>,
+    /// The entity with the `RainArea` the bolt strikes in.
+    pub area: Entity,
+}
+
+/// How bright the sky is lit up by lightning right now, from 0 to 1.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lightning {
+    pub flash: f32,
+}
+
+/// A segment of a lightning bolt.
+pub(crate) struct Bolt {
+    lifetime: Timer,
+}
+
+/// Makes a jagged line from `from` to `to`
+/// by moving the middle of each segment aside, `detail` times over.
+pub fn bolt_path(rng: &mut impl Rng, from: Vec2, to: Vec2, detail: usize) -> Vec<Vec2> {
+    if from == to {
+        return vec![from];
+    }
+
+    let mut points = vec![from, to];
+    let mut offset = (to - from).length() / 4.;
+    for _ in 0..detail {
+        let mut jagged = Vec::with_capacity(points.len() * 2);
+        for pair in points.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let direction = b - a;
+            let normal = Vec2::new(-direction.y, direction.x).normalize();
+            jagged.push(a);
+            jagged.push((a + b) / 2. + normal * rng.gen_range(-offset..offset));
+        }
+        jagged.push(to);
+        points = jagged;
+        offset /= 2.;
+    }
+    points
+}
+
+pub(crate) fn strike_lightning(
+    mut lightning: ResMut<Lightning>,
+    mut strikes: EventWriter<LightningStrike>,
+    mut rng: ResMut<SmallRng>,
+    weather: Res<Weather>,
+    areas: Query<(Entity, &Viewport)>,
+    config: Res<RainConfig>,
+) {
+    let lightning_config = &config.lightning;
+    lightning.flash *= (-config.timestep * 3. / lightning_config.decay).exp();
+
+    if weather.kind() != WeatherKind::Storm {
+        return;
+    }
+    if !rng.gen_bool((lightning_config.frequency * config.timestep).min(1.) as f64) {
+        return;
+    }
+
+    let intensity = rng.gen_range(0.5..1.);
+    lightning.flash = lightning.flash.max(intensity);
+    for (area, viewport) in areas.iter() {
+        if viewport.width <= 0. {
+            continue;
+        }
+        let bolt = if lightning_config.bolts {
+            let x = rng.gen_range(viewport.left()..viewport.right());
+            let from = Vec2::new(x, viewport.top());
+            let to = Vec2::new(
+                x + rng.gen_range(-0.2..0.2) * viewport.width,
+                viewport.bottom(),
+            );
+            bolt_path(&mut *rng, from, to, 5)
+        } else {
+            Vec::new()
+        };
+        strikes.send(LightningStrike {
+            intensity,
+            bolt,
+            area,
+        });
+    }
+}
+
+pub(crate) fn flash_background(
+    lightning: Res<Lightning>,
+    background_query: Query<&Handle<Uniforms>, With<Background>>,
+    mut uniforms: ResMut<Assets<Uniforms>>,
+) {
+    for handle in background_query.iter() {
+        let flashing = uniforms
+            .get(handle)
+            .map_or(false, |uniform| uniform.flash != lightning.flash);
+        if flashing {
+            if let Some(uniform) = uniforms.get_mut(handle) {
+                uniform.flash = lightning.flash;
+            }
+        }
+    }
+}
+
+pub(crate) fn draw_bolts(
+    commands: &mut Commands,
+    mut strikes: EventReader<LightningStrike>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut material: Local<Option<Handle<ColorMaterial>>>,
+    config: Res<RainConfig>,
+) {
+    for strike in strikes.iter() {
+        let material = material
+            .get_or_insert_with(|| materials.add(Color::rgb(1., 1., 0.95).into()))
+            .clone();
+        for pair in strike.bolt.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let direction = b - a;
+            commands
+                .spawn(SpriteBundle {
+                    material: material.clone(),
+                    sprite: Sprite::new(Vec2::new(3. * strike.intensity, direction.length())),
+                    transform: Transform {
+                        translation: ((a + b) / 2.).extend(0.5),
+                        rotation: Quat::from_rotation_z(-direction.x.atan2(direction.y)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .with(Bolt {
+                    lifetime: Timer::from_seconds(config.lightning.decay, false),
+                })
+                .with(InArea(strike.area));
+        }
+    }
+}
+
+pub(crate) fn fade_bolts(
+    commands: &mut Commands,
+    mut bolts: Query<(Entity, &mut Bolt)>,
+    time: Res<Time>,
+) {
+    for (entity, mut bolt) in bolts.iter_mut() {
+        if bolt.lifetime.tick(time.delta_seconds()).finished() {
+            commands.despawn(entity);
+        }
+    }
+}
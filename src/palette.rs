@@ -0,0 +1,132 @@
+use crate::{Clock, DepthLayer, RainConfig};
+use bevy::prelude::*;
+
+const SHADES: usize = 8;
+const FADES: usize = 8;
+
+/// The materials every drop and splash of an area is drawn with, created once and shared.
+pub struct DropMaterials {
+    color: Color,
+    /// How opaque each of the layers is.
+    alphas: Vec<f32>,
+    /// The shades of each layer.
+    shades: Vec<Vec<Handle<ColorMaterial>>>,
+    fades: Vec<Handle<ColorMaterial>>,
+}
+
+impl DropMaterials {
+    pub(crate) fn new(materials: &mut Assets<ColorMaterial>, config: &RainConfig) -> Self {
+        let mut palette = Self {
+            color: config.drop_color,
+            alphas: Vec::new(),
+            shades: Vec::new(),
+            fades: (0..FADES)
+                .map(|i| materials.add(fade(config.drop_color, i).into()))
+                .collect(),
+        };
+        palette.recolor(materials, config.drop_color, &config.layers);
+        palette
+    }
+
+    /// The colour the materials are shades and fades of.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// The colour of the drops in the layer at `layer`.
+    pub fn layer_color(&self, layer: usize) -> Color {
+        let alpha = self
+            .alphas
+            .get(layer)
+            .or_else(|| self.alphas.last())
+            .copied()
+            .unwrap_or(1.);
+        transparent(self.color, alpha)
+    }
+
+    /// One of the slightly darker or brighter shades of the colour of `layer`, from 0 to 1.
+    pub fn shade(&self, layer: usize, brightness: f32) -> Handle<ColorMaterial> {
+        let shades = &self.shades[layer.min(self.shades.len() - 1)];
+        shades[index(brightness, SHADES)].clone()
+    }
+
+    /// The drop colour faded out by `fade`, from 0 to 1.
+    pub fn fade(&self, fade: f32) -> Handle<ColorMaterial> {
+        self.fades[index(fade, FADES)].clone()
+    }
+
+    /// Colours the materials in `color`, adding or removing shades as the `layers` need.
+    fn recolor(
+        &mut self,
+        materials: &mut Assets<ColorMaterial>,
+        color: Color,
+        layers: &[DepthLayer],
+    ) {
+        self.color = color;
+        self.alphas = layers.iter().map(|layer| layer.alpha).collect();
+        self.shades.resize_with(layers.len().max(1), || {
+            (0..SHADES)
+                .map(|_| materials.add(ColorMaterial::default()))
+                .collect()
+        });
+        for (layer, shades) in self.shades.iter().enumerate() {
+            let color = self.layer_color(layer);
+            for (i, handle) in shades.iter().enumerate() {
+                if let Some(material) = materials.get_mut(handle) {
+                    material.color = shade(color, i as f32 / (SHADES - 1) as f32);
+                }
+            }
+        }
+        for (i, handle) in self.fades.iter().enumerate() {
+            if let Some(material) = materials.get_mut(handle) {
+                material.color = fade(color, i);
+            }
+        }
+    }
+}
+
+fn index(value: f32, len: usize) -> usize {
+    ((value.max(0.) * len as f32) as usize).min(len - 1)
+}
+
+/// Makes `color` a bit darker or brighter, from 0 for the darkest to 1 for the brightest shade.
+pub(crate) fn shade(color: Color, brightness: f32) -> Color {
+    let brightness = 0.85 + 0.3 * brightness.max(0.).min(1.);
+    Color::rgba(
+        (color.r() * brightness).min(1.),
+        (color.g() * brightness).min(1.),
+        (color.b() * brightness).min(1.),
+        color.a(),
+    )
+}
+
+fn fade(color: Color, i: usize) -> Color {
+    transparent(color, 1. - i as f32 / FADES as f32)
+}
+
+fn transparent(color: Color, alpha: f32) -> Color {
+    let mut color = color;
+    color.set_a(color.a() * alpha);
+    color
+}
+
+/// Keeps the materials of every area in its drop colour and layers, tinted by the time of day.
+pub(crate) fn tint_drops(
+    mut areas: Query<(&mut DropMaterials, &RainConfig)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    clock: Res<Clock>,
+    config: Res<RainConfig>,
+) {
+    for (mut palette, area_config) in areas.iter_mut() {
+        let color = clock.tint(area_config.drop_color, &config.day_night);
+        let layers_changed = palette.alphas.len() != area_config.layers.len()
+            || palette
+                .alphas
+                .iter()
+                .zip(&area_config.layers)
+                .any(|(alpha, layer)| *alpha != layer.alpha);
+        if palette.color != color || layers_changed {
+            palette.recolor(&mut materials, color, &area_config.layers);
+        }
+    }
+}
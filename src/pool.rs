@@ -0,0 +1,125 @@
+use crate::Drop;
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+};
+
+/// Keeps drops that are out of use around so they can be used again instead of spawning new ones.
+///
+/// Each `RainArea` has its own.
+#[derive(Debug, Default)]
+pub struct DropPool {
+    free: Vec<Entity>,
+    active: usize,
+}
+
+impl DropPool {
+    /// How many drops are falling right now.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// How many drops are waiting to be used again.
+    pub fn pooled(&self) -> usize {
+        self.free.len()
+    }
+
+    /// How many drop entities there are in total.
+    pub fn len(&self) -> usize {
+        self.active + self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes a drop out of the pool to be reset and used again.
+    ///
+    /// It is not counted until it is `add`ed again, so a drop that is gone by now is forgotten.
+    pub(crate) fn take(&mut self) -> Option<Entity> {
+        self.free.pop()
+    }
+
+    /// Counts a drop that starts falling, either newly spawned or taken out of the pool.
+    pub(crate) fn add(&mut self) {
+        self.active += 1;
+    }
+
+    /// Puts a drop that is done falling back into the pool.
+    pub(crate) fn retire(&mut self, entity: Entity, drop: &mut Drop) {
+        if drop.active {
+            drop.active = false;
+            self.active -= 1;
+            self.free.push(entity);
+        }
+    }
+}
+
+/// Adds diagnostics for the number of active and pooled drops in all areas together.
+#[derive(Default)]
+pub struct DropPoolDiagnosticsPlugin;
+
+impl DropPoolDiagnosticsPlugin {
+    pub const ACTIVE_DROPS: DiagnosticId =
+        DiagnosticId::from_u128(326185372385011849234857301826357913482);
+    pub const POOLED_DROPS: DiagnosticId =
+        DiagnosticId::from_u128(98143726123598112309480213847218340756);
+
+    fn setup_system(mut diagnostics: ResMut<Diagnostics>) {
+        diagnostics.add(Diagnostic::new(Self::ACTIVE_DROPS, "active_drops", 20));
+        diagnostics.add(Diagnostic::new(Self::POOLED_DROPS, "pooled_drops", 20));
+    }
+
+    fn diagnostic_system(mut diagnostics: ResMut<Diagnostics>, pools: Query<&DropPool>) {
+        let active = pools.iter().map(DropPool::active).sum::<usize>();
+        let pooled = pools.iter().map(DropPool::pooled).sum::<usize>();
+        diagnostics.add_measurement(Self::ACTIVE_DROPS, active as f64);
+        diagnostics.add_measurement(Self::POOLED_DROPS, pooled as f64);
+    }
+}
+
+impl Plugin for DropPoolDiagnosticsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(Self::setup_system.system())
+            .add_system(Self::diagnostic_system.system());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drop() -> Drop {
+        Drop {
+            length: 10.,
+            size: 0.5,
+            terminal_velocity: 500.,
+            layer: 0,
+            area: Entity::new(0),
+            active: true,
+        }
+    }
+
+    #[test]
+    fn retired_drops_are_pooled() {
+        let mut pool = DropPool::default();
+        let mut drop = drop();
+        pool.add();
+        pool.retire(Entity::new(1), &mut drop);
+        pool.retire(Entity::new(1), &mut drop);
+        assert!(!drop.is_active());
+        assert_eq!((pool.active(), pool.pooled()), (0, 1));
+    }
+
+    #[test]
+    fn taken_drops_only_count_once_added() {
+        let mut pool = DropPool::default();
+        pool.add();
+        pool.retire(Entity::new(1), &mut drop());
+        assert_eq!(pool.take(), Some(Entity::new(1)));
+        assert!(pool.is_empty());
+        pool.add();
+        assert_eq!((pool.active(), pool.pooled()), (1, 0));
+        assert_eq!(pool.take(), None);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{area::MainArea, Clock, RainConfig, SkyGradient, Weather, Wind};
+use bevy::prelude::*;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+/// Sent after the configuration file was changed and the new configuration is in use.
+pub struct ConfigChanged;
+
+/// Changes made to every configuration, whether it was given to the plugin or reloaded.
+pub(crate) type Overrides = Arc<dyn Fn(&mut RainConfig) + Send + Sync>;
+
+pub(crate) struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    timer: Timer,
+    overrides: Option<Overrides>,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn new(path: PathBuf, overrides: Option<Overrides>) -> Self {
+        Self {
+            modified: modified(&path),
+            path,
+            timer: Timer::from_seconds(0.5, true),
+            overrides,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+pub(crate) fn watch_config(
+    mut watcher: ResMut<ConfigWatcher>,
+    mut config: ResMut<RainConfig>,
+    mut main_areas: Query<&mut RainConfig, With<MainArea>>,
+    mut wind: ResMut<Wind>,
+    mut weather: ResMut<Weather>,
+    mut sky: ResMut<SkyGradient>,
+    mut clock: ResMut<Clock>,
+    mut config_changed_events: EventWriter<ConfigChanged>,
+    time: Res<Time>,
+) {
+    if !watcher.timer.tick(time.delta_seconds()).just_finished() {
+        return;
+    }
+
+    let modified = modified(&watcher.path);
+    if modified == watcher.modified {
+        return;
+    }
+    watcher.modified = modified;
+
+    match RainConfig::load(&watcher.path) {
+        Ok(mut new_config) => {
+            info!("reloaded {}", watcher.path.display());
+            if let Some(overrides) = &watcher.overrides {
+                overrides(&mut new_config);
+            }
+            if new_config.timestep != config.timestep {
+                warn!("the timestep can only be changed by restarting");
+                new_config.timestep = config.timestep;
+            }
+            if new_config.render_mode != config.render_mode {
+                warn!("the render mode can only be changed by restarting");
+                new_config.render_mode = config.render_mode;
+            }
+            // Keep changes made through the `Wind` resource unless the file changes the wind too.
+            if new_config.wind != config.wind {
+                wind.direction = new_config.wind.direction;
+                wind.strength = new_config.wind.strength;
+                wind.gustiness = new_config.wind.gustiness;
+                wind.gust_frequency = new_config.wind.gust_frequency;
+            }
+            if new_config.weather != config.weather {
+                weather.schedule(
+                    new_config.weather.schedule.clone(),
+                    new_config.weather.looping,
+                );
+            }
+            // Likewise for changes made through the `Clock`, like freezing it.
+            if new_config.day_night != config.day_night {
+                clock.cycle = new_config.day_night.cycle;
+                clock.frozen = new_config.day_night.frozen;
+            }
+            if new_config.sky != config.sky {
+                *sky = new_config.sky.clone();
+            }
+            for mut area_config in main_areas.iter_mut() {
+                *area_config = new_config.clone();
+            }
+            *config = new_config;
+            config_changed_events.send(ConfigChanged);
+        }
+        Err(err) => warn!("{}: {}", watcher.path.display(), err),
+    }
+}
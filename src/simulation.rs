@@ -0,0 +1,60 @@
+use crate::{Drop, Velocity, Wind};
+use bevy::{core::FixedTimesteps, prelude::*};
+
+/// The stage the rain is simulated in,
+/// once for every fixed timestep that passed since the last frame.
+///
+/// Its systems run one after the other in the order they were added,
+/// so the same seed results in the same rain no matter how the threads are scheduled.
+pub const SIMULATION_STAGE: &str = "rain_simulation";
+/// The label of the fixed timestep of the `SIMULATION_STAGE` in `FixedTimesteps`.
+pub const TIMESTEP: &str = "rain_timestep";
+
+/// Where an entity is in the simulation.
+///
+/// The simulation moves in fixed steps, so to move smoothly, the `Transform` is put
+/// between the previous and the current position depending on how far the next step is.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Position {
+    pub current: Vec2,
+    pub previous: Vec2,
+}
+
+impl Position {
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            current: position,
+            previous: position,
+        }
+    }
+
+    /// Moves to `position` over the course of one step.
+    pub fn move_to(&mut self, position: Vec2) {
+        self.previous = self.current;
+        self.current = position;
+    }
+
+    /// The position `t` of the way from the previous to the current position.
+    pub fn lerp(&self, t: f32) -> Vec2 {
+        self.previous + (self.current - self.previous) * t
+    }
+}
+
+pub(crate) fn interpolate(
+    mut entities: Query<(&Position, Option<&Velocity>, Option<&Drop>, &mut Transform)>,
+    timesteps: Res<FixedTimesteps>,
+) {
+    let t = timesteps
+        .get(TIMESTEP)
+        .map_or(1., |timestep| timestep.overstep_percentage() as f32)
+        .min(1.);
+    for (position, velocity, drop, mut transform) in entities.iter_mut() {
+        if drop.map_or(false, |drop| !drop.is_active()) {
+            continue;
+        }
+        transform.translation = position.lerp(t).extend(transform.translation.z);
+        if let (Some(_), Some(velocity)) = (drop, velocity) {
+            transform.rotation = Wind::tilt(velocity.0);
+        }
+    }
+}
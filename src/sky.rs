@@ -0,0 +1,286 @@
+use crate::{config::rgba, Background, Uniforms};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt};
+
+/// A colour of the `SkyGradient` at a certain height.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// How high up the colour is, from 0 at the bottom to 1 at the top.
+    pub position: f32,
+    #[serde(with = "rgba")]
+    pub color: Color,
+}
+
+/// The gradient of the background.
+///
+/// It can be changed at any time to match the weather or the time of day.
+/// It always has from 1 to `MAX_STOPS` colours, ordered from the bottom to the top.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SkyGradient {
+    stops: Vec<GradientStop>,
+}
+
+/// Why colours can't make up a `SkyGradient`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradientError {
+    /// There are no colours, or more than `SkyGradient::MAX_STOPS`.
+    Count,
+    /// The colour at this index is not between the position of the one before and 1.
+    Position(usize),
+    /// A component of the colour at this index is not between 0 and 1.
+    Color(usize),
+}
+
+impl fmt::Display for GradientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GradientError::Count => write!(
+                f,
+                "a sky gradient must have from 1 to {} colours",
+                SkyGradient::MAX_STOPS
+            ),
+            GradientError::Position(i) => write!(
+                f,
+                "colour {} must be between the position of the one before and 1",
+                i
+            ),
+            GradientError::Color(i) => {
+                write!(f, "every component of colour {} must be between 0 and 1", i)
+            }
+        }
+    }
+}
+
+impl Error for GradientError {}
+
+impl SkyGradient {
+    pub const MAX_STOPS: usize = 4;
+
+    pub fn new(bottom: Color, top: Color) -> Self {
+        Self {
+            stops: vec![
+                GradientStop {
+                    position: 0.,
+                    color: bottom,
+                },
+                GradientStop {
+                    position: 1.,
+                    color: top,
+                },
+            ],
+        }
+    }
+
+    /// The colours from bottom to top.
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+
+    /// Replaces all colours, which have to be ordered from the bottom to the top.
+    pub fn set_stops(&mut self, stops: Vec<GradientStop>) -> Result<(), GradientError> {
+        check_stops(&stops)?;
+        self.stops = stops;
+        Ok(())
+    }
+
+    /// Adds another colour at `position`, from 0 at the bottom to 1 at the top.
+    ///
+    /// Fails if there are `MAX_STOPS` colours already or `position` is not between 0 and 1.
+    pub fn with_stop(mut self, position: f32, color: Color) -> Result<Self, GradientError> {
+        let index = self
+            .stops
+            .iter()
+            .position(|stop| stop.position > position)
+            .unwrap_or_else(|| self.stops.len());
+        let mut stops = self.stops.clone();
+        stops.insert(index, GradientStop { position, color });
+        self.set_stops(stops)?;
+        Ok(self)
+    }
+
+    /// The gradient `t` of the way from this one to `other`, from 0 to 1.
+    pub fn lerp(&self, other: &SkyGradient, t: f32) -> SkyGradient {
+        let len = self.stops.len().max(other.stops.len());
+        let stop = |gradient: &SkyGradient, i: usize| {
+            gradient
+                .stops
+                .get(i)
+                .or_else(|| gradient.stops.last())
+                .copied()
+        };
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        SkyGradient {
+            stops: (0..len)
+                .filter_map(|i| {
+                    let (a, b) = (stop(self, i)?, stop(other, i)?);
+                    Some(GradientStop {
+                        position: lerp(a.position, b.position),
+                        color: Color::rgba(
+                            lerp(a.color.r(), b.color.r()),
+                            lerp(a.color.g(), b.color.g()),
+                            lerp(a.color.b(), b.color.b()),
+                            lerp(a.color.a(), b.color.a()),
+                        ),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// The colours as the columns of a matrix and their positions,
+    /// with the last stop repeated to fill up all `MAX_STOPS`.
+    pub(crate) fn pack(&self) -> (Mat4, Vec4) {
+        let last = self.stops.last().copied().unwrap_or(GradientStop {
+            position: 1.,
+            color: Color::BLACK,
+        });
+        let stop = |i: usize| self.stops.get(i).copied().unwrap_or(last);
+        let color = |i: usize| {
+            let color = stop(i).color;
+            Vec4::new(
+                linear(color.r()),
+                linear(color.g()),
+                linear(color.b()),
+                color.a(),
+            )
+        };
+        (
+            Mat4::from_cols(color(0), color(1), color(2), color(3)),
+            Vec4::new(
+                stop(0).position,
+                stop(1).position,
+                stop(2).position,
+                stop(3).position,
+            ),
+        )
+    }
+}
+
+impl Default for SkyGradient {
+    fn default() -> Self {
+        Self::new(Color::rgb(0., 1., 1.), Color::rgb(1., 1., 1.))
+    }
+}
+
+/// Makes sure `stops` are from 1 to `MAX_STOPS` valid colours ordered from the bottom to the top.
+pub(crate) fn check_stops(stops: &[GradientStop]) -> Result<(), GradientError> {
+    if stops.is_empty() || stops.len() > SkyGradient::MAX_STOPS {
+        return Err(GradientError::Count);
+    }
+    let mut previous = 0.;
+    for (i, stop) in stops.iter().enumerate() {
+        if !(previous..=1.).contains(&stop.position) {
+            return Err(GradientError::Position(i));
+        }
+        previous = stop.position;
+        let color = stop.color;
+        let components = [color.r(), color.g(), color.b(), color.a()];
+        if !components.iter().all(|c| (0.0..=1.).contains(c)) {
+            return Err(GradientError::Color(i));
+        }
+    }
+    Ok(())
+}
+
+/// Converts an sRGB colour component to linear, which is what the shader works with.
+fn linear(component: f32) -> f32 {
+    if component <= 0.04045 {
+        component / 12.92
+    } else {
+        ((component + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn paint_sky(
+    sky: Res<SkyGradient>,
+    background_query: Query<&Handle<Uniforms>, With<Background>>,
+    mut uniforms: ResMut<Assets<Uniforms>>,
+) {
+    let (colors, stops) = sky.pack();
+    for handle in background_query.iter() {
+        let outdated = uniforms.get(handle).map_or(false, |uniform| {
+            uniform.colors != colors || uniform.stops != stops
+        });
+        if outdated {
+            if let Some(uniform) = uniforms.get_mut(handle) {
+                uniform.colors = colors;
+                uniform.stops = stops;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(gradient: &SkyGradient) -> Vec<f32> {
+        gradient.stops().iter().map(|stop| stop.position).collect()
+    }
+
+    #[test]
+    fn stops_are_sorted_by_position() {
+        let gradient = SkyGradient::new(Color::BLACK, Color::WHITE)
+            .with_stop(0.5, Color::RED)
+            .unwrap();
+        assert_eq!(positions(&gradient), vec![0., 0.5, 1.]);
+    }
+
+    #[test]
+    fn no_more_than_max_stops() {
+        let gradient = SkyGradient::new(Color::BLACK, Color::WHITE)
+            .with_stop(0.25, Color::RED)
+            .and_then(|gradient| gradient.with_stop(0.5, Color::GREEN))
+            .unwrap();
+        assert_eq!(gradient.stops().len(), SkyGradient::MAX_STOPS);
+        assert_eq!(
+            gradient.clone().with_stop(0.75, Color::BLUE),
+            Err(GradientError::Count)
+        );
+        assert_eq!(positions(&gradient), vec![0., 0.25, 0.5, 1.]);
+    }
+
+    #[test]
+    fn stops_are_between_the_bottom_and_the_top() {
+        let gradient = SkyGradient::new(Color::BLACK, Color::WHITE);
+        assert_eq!(
+            gradient.clone().with_stop(1.5, Color::RED),
+            Err(GradientError::Position(2))
+        );
+        assert_eq!(
+            gradient.with_stop(-0.5, Color::RED),
+            Err(GradientError::Position(0))
+        );
+    }
+
+    #[test]
+    fn set_stops_keeps_the_gradient_if_they_are_invalid() {
+        let mut gradient = SkyGradient::default();
+        let stop = |position: f32, color: Color| GradientStop { position, color };
+        assert_eq!(gradient.set_stops(Vec::new()), Err(GradientError::Count));
+        assert_eq!(
+            gradient.set_stops(vec![stop(0.6, Color::BLACK), stop(0.3, Color::WHITE)]),
+            Err(GradientError::Position(1))
+        );
+        assert_eq!(
+            gradient.set_stops(vec![stop(0., Color::rgba(2., 0., 0., 1.))]),
+            Err(GradientError::Color(0))
+        );
+        assert_eq!(gradient, SkyGradient::default());
+
+        assert!(gradient
+            .set_stops(vec![stop(0.2, Color::BLACK), stop(0.8, Color::WHITE)])
+            .is_ok());
+        assert_eq!(positions(&gradient), vec![0.2, 0.8]);
+    }
+
+    #[test]
+    fn packing_repeats_the_last_stop() {
+        let (colors, stops) = SkyGradient::new(Color::BLACK, Color::WHITE).pack();
+        assert_eq!(stops, Vec4::new(0., 1., 1., 1.));
+        let (black, white) = (Vec4::new(0., 0., 0., 1.), Vec4::new(1., 1., 1., 1.));
+        assert_eq!(colors, Mat4::from_cols(black, white, white, white));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{area::InArea, layer, DropMaterials, Position, RainConfig, Velocity};
+use bevy::prelude::*;
+use rand::rngs::SmallRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Sent when a drop hits something and is gone.
+#[derive(Clone, Copy, Debug)]
+pub struct DropImpact {
+    pub position: Vec2,
+    /// The velocity the drop had when it hit.
+    pub velocity: Vec2,
+    /// The entity with the `Collider` that was hit
+    /// or `None` if the drop fell out of the bottom of the viewport.
+    pub collider: Option<Entity>,
+    /// The entity with the `RainArea` the drop fell in.
+    pub area: Entity,
+    /// Which of the `RainConfig::layers` the drop fell in.
+    pub layer: usize,
+}
+
+/// How drops splash when they hit something.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SplashConfig {
+    /// How many droplets a drop splashes into.
+    pub count: usize,
+    pub size: Range<f32>,
+    /// The highest speed in pixels per second a droplet is thrown up with.
+    pub speed: f32,
+    /// Seconds until a droplet has faded out.
+    pub lifetime: f32,
+}
+
+impl Default for SplashConfig {
+    fn default() -> Self {
+        Self {
+            count: 3,
+            size: 1.0..3.,
+            speed: 250.,
+            lifetime: 0.4,
+        }
+    }
+}
+
+/// A droplet of a splash.
+pub struct Splash {
+    pub size: f32,
+    /// The entity with the `RainArea` the droplet splashed in.
+    pub area: Entity,
+    lifetime: Timer,
+}
+
+pub(crate) fn splash(
+    commands: &mut Commands,
+    mut impacts: EventReader<DropImpact>,
+    mut rng: ResMut<SmallRng>,
+    areas: Query<&RainConfig>,
+) {
+    for impact in impacts.iter() {
+        let splash = match areas.get(impact.area) {
+            Ok(config) => &config.splash,
+            Err(_) => continue,
+        };
+        // As far from the camera as the drop that hit.
+        let depth = layer::depth(impact.layer);
+        for _ in 0..splash.count {
+            let velocity = Vec2::new(
+                rng.gen_range(-0.5..0.5) * splash.speed + impact.velocity.x * 0.2,
+                rng.gen_range(0.3..1.) * splash.speed,
+            );
+            commands
+                .spawn((
+                    Transform::from_translation(impact.position.extend(depth)),
+                    GlobalTransform::default(),
+                ))
+                .with(Splash {
+                    size: rng.gen_range(splash.size.clone()),
+                    area: impact.area,
+                    lifetime: Timer::from_seconds(splash.lifetime, false),
+                })
+                .with(Velocity(velocity))
+                .with(Position::new(impact.position));
+        }
+    }
+}
+
+pub(crate) fn move_splashes(
+    commands: &mut Commands,
+    mut splashes: Query<(Entity, &mut Splash, &mut Velocity, &mut Position)>,
+    areas: Query<&RainConfig>,
+    config: Res<RainConfig>,
+) {
+    let delta = config.timestep;
+    for (entity, mut splash, mut velocity, mut position) in splashes.iter_mut() {
+        if splash.lifetime.tick(delta).finished() {
+            commands.despawn(entity);
+            continue;
+        }
+        let gravity = areas
+            .get(splash.area)
+            .map_or(config.gravity, |area_config| area_config.gravity);
+        velocity.0.y -= gravity * delta;
+        let to = position.current + velocity.0 * delta;
+        position.move_to(to);
+    }
+}
+
+pub(crate) fn add_splash_sprites(
+    commands: &mut Commands,
+    splashes: Query<(Entity, &Splash, &Transform), Added<Splash>>,
+    palettes: Query<&DropMaterials>,
+) {
+    for (entity, splash, transform) in splashes.iter() {
+        let palette = match palettes.get(splash.area) {
+            Ok(palette) => palette,
+            Err(_) => continue,
+        };
+        commands.insert(
+            entity,
+            SpriteBundle {
+                material: palette.fade(0.),
+                sprite: Sprite::new(Vec2::new(splash.size, splash.size)),
+                transform: *transform,
+                global_transform: GlobalTransform::from(*transform),
+                ..Default::default()
+            },
+        );
+        commands.insert_one(entity, InArea(splash.area));
+    }
+}
+
+pub(crate) fn fade_splashes(
+    mut splashes: Query<(&Splash, &mut Handle<ColorMaterial>)>,
+    palettes: Query<&DropMaterials>,
+) {
+    for (splash, mut material) in splashes.iter_mut() {
+        let palette = match palettes.get(splash.area) {
+            Ok(palette) => palette,
+            Err(_) => continue,
+        };
+        let faded = palette.fade(splash.lifetime.percent());
+        if *material != faded {
+            *material = faded;
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::RainArea;
+use bevy::prelude::*;
+
+/// The area the rain falls in, in world coordinates.
+///
+/// Each `RainArea` has its own. With a window this follows what the camera of the area sees,
+/// including where it moved and how far it zoomed. Headless areas keep the one they started with.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Viewport {
+    pub width: f32,
+    pub height: f32,
+    pub center: Vec2,
+}
+
+impl Viewport {
+    /// A viewport centred on the origin.
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            center: Vec2::zero(),
+        }
+    }
+
+    pub fn with_center(mut self, center: Vec2) -> Self {
+        self.center = center;
+        self
+    }
+
+    pub fn left(&self) -> f32 {
+        self.center.x - self.width / 2.
+    }
+
+    pub fn right(&self) -> f32 {
+        self.center.x + self.width / 2.
+    }
+
+    pub fn top(&self) -> f32 {
+        self.center.y + self.height / 2.
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.center.y - self.height / 2.
+    }
+
+    /// Whether `position` is no farther than `margin` outside of the viewport.
+    pub fn contains(&self, position: Vec2, margin: f32) -> bool {
+        position.x >= self.left() - margin
+            && position.x <= self.right() + margin
+            && position.y >= self.bottom() - margin
+            && position.y <= self.top() + margin
+    }
+}
+
+pub(crate) fn follow_camera(
+    mut areas: Query<(&RainArea, &mut Viewport)>,
+    cameras: Query<(&OrthographicProjection, &GlobalTransform)>,
+) {
+    for (area, mut viewport) in areas.iter_mut() {
+        let (projection, transform) =
+            match area.camera().and_then(|camera| cameras.get(camera).ok()) {
+                Some(camera) => camera,
+                None => continue,
+            };
+        let scale = transform.scale.truncate();
+        let offset = Vec2::new(
+            projection.left + projection.right,
+            projection.bottom + projection.top,
+        ) / 2.
+            * scale;
+        let visible = Viewport::new(
+            (projection.right - projection.left) * scale.x,
+            (projection.top - projection.bottom) * scale.y,
+        )
+        .with_center(transform.translation.truncate() + offset);
+        if *viewport != visible {
+            *viewport = visible;
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use crate::RainConfig;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The kinds of weather there can be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WeatherKind {
+    Clear,
+    Drizzle,
+    Rain,
+    Storm,
+}
+
+impl WeatherKind {
+    /// What the rain is like in this weather.
+    pub fn conditions(&self) -> Conditions {
+        match self {
+            WeatherKind::Clear => Conditions {
+                intensity: 0.,
+                wind: 0.3,
+                drop_length: 1.,
+            },
+            WeatherKind::Drizzle => Conditions {
+                intensity: 0.2,
+                wind: 0.5,
+                drop_length: 0.6,
+            },
+            WeatherKind::Rain => Conditions {
+                intensity: 1.,
+                wind: 1.,
+                drop_length: 1.,
+            },
+            WeatherKind::Storm => Conditions {
+                intensity: 3.,
+                wind: 2.5,
+                drop_length: 1.3,
+            },
+        }
+    }
+}
+
+/// Factors the configured intensity, wind strength and drop length are multiplied by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conditions {
+    pub intensity: f32,
+    pub wind: f32,
+    pub drop_length: f32,
+}
+
+impl Conditions {
+    fn lerp(&self, other: &Conditions, t: f32) -> Conditions {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        Conditions {
+            intensity: lerp(self.intensity, other.intensity),
+            wind: lerp(self.wind, other.wind),
+            drop_length: lerp(self.drop_length, other.drop_length),
+        }
+    }
+}
+
+/// A planned change of the weather.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeatherStep {
+    /// Seconds to wait after the previous change is done.
+    pub after: f32,
+    pub weather: WeatherKind,
+    /// Seconds it takes to change to `weather`.
+    pub transition: f32,
+}
+
+/// The weather the rain is in at the start and how it changes over time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherConfig {
+    pub initial: WeatherKind,
+    /// The changes of the weather, one after another.
+    pub schedule: Vec<WeatherStep>,
+    /// Whether to start over once the schedule is done.
+    pub looping: bool,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            initial: WeatherKind::Rain,
+            schedule: Vec::new(),
+            looping: false,
+        }
+    }
+}
+
+/// Sent when the weather starts or finishes changing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeatherEvent {
+    Changing { from: WeatherKind, to: WeatherKind },
+    Changed { weather: WeatherKind },
+}
+
+/// The current weather, which smoothly changes the rain from one kind of weather to another.
+#[derive(Clone, Debug)]
+pub struct Weather {
+    kind: WeatherKind,
+    conditions: Conditions,
+    from: Conditions,
+    transition: Option<Transition>,
+    announce: Option<WeatherKind>,
+    schedule: Vec<WeatherStep>,
+    looping: bool,
+    next_step: usize,
+    waited: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Transition {
+    elapsed: f32,
+    duration: f32,
+}
+
+impl Weather {
+    pub fn new(config: &WeatherConfig) -> Self {
+        Self {
+            kind: config.initial,
+            conditions: config.initial.conditions(),
+            from: config.initial.conditions(),
+            transition: None,
+            announce: None,
+            schedule: config.schedule.clone(),
+            looping: config.looping,
+            next_step: 0,
+            waited: 0.,
+        }
+    }
+
+    /// The weather it is or, while changing, the weather it is changing to.
+    pub fn kind(&self) -> WeatherKind {
+        self.kind
+    }
+
+    /// What the rain is like right now.
+    pub fn conditions(&self) -> Conditions {
+        self.conditions
+    }
+
+    pub fn is_changing(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Starts changing the weather to `kind` over the course of `seconds`.
+    pub fn change_to(&mut self, kind: WeatherKind, seconds: f32) {
+        self.announce = Some(self.kind);
+        self.from = self.conditions;
+        self.kind = kind;
+        self.transition = Some(Transition {
+            elapsed: 0.,
+            duration: seconds,
+        });
+    }
+
+    /// Replaces the planned changes of the weather. The first one is waited for from now on.
+    pub fn schedule(&mut self, schedule: Vec<WeatherStep>, looping: bool) {
+        self.schedule = schedule;
+        self.looping = looping;
+        self.next_step = 0;
+        self.waited = 0.;
+    }
+}
+
+pub(crate) fn update_weather(
+    mut weather: ResMut<Weather>,
+    mut weather_events: EventWriter<WeatherEvent>,
+    config: Res<RainConfig>,
+) {
+    let delta = config.timestep;
+
+    if let Some(from) = weather.announce.take() {
+        weather_events.send(WeatherEvent::Changing {
+            from,
+            to: weather.kind,
+        });
+    }
+
+    if let Some(mut transition) = weather.transition {
+        transition.elapsed += delta;
+        if transition.elapsed >= transition.duration {
+            weather.transition = None;
+            weather.conditions = weather.kind.conditions();
+            weather_events.send(WeatherEvent::Changed {
+                weather: weather.kind,
+            });
+        } else {
+            let t = transition.elapsed / transition.duration;
+            let smooth = t * t * (3. - 2. * t);
+            weather.conditions = weather.from.lerp(&weather.kind.conditions(), smooth);
+            weather.transition = Some(transition);
+        }
+        return;
+    }
+
+    if let Some(step) = weather.schedule.get(weather.next_step).copied() {
+        weather.waited += delta;
+        if weather.waited >= step.after {
+            weather.waited = 0.;
+            weather.next_step += 1;
+            if weather.looping && weather.next_step == weather.schedule.len() {
+                weather.next_step = 0;
+            }
+            weather.change_to(step.weather, step.transition);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Sent(Vec<WeatherEvent>);
+
+    fn collect(mut sent: ResMut<Sent>, mut weather_events: EventReader<WeatherEvent>) {
+        sent.0.extend(weather_events.iter().copied());
+    }
+
+    /// Runs `update_weather` once per update, each a step of `timestep` seconds.
+    fn app(config: WeatherConfig, timestep: f32) -> App {
+        let mut app = App::build();
+        app.insert_resource(Weather::new(&config))
+            .insert_resource(RainConfig {
+                timestep,
+                weather: config,
+                ..Default::default()
+            })
+            .init_resource::<Sent>()
+            .add_event::<WeatherEvent>()
+            .add_system(update_weather.system())
+            .add_system(collect.system());
+        app.app
+    }
+
+    fn update(app: &mut App, steps: usize) {
+        for _ in 0..steps {
+            app.update();
+        }
+    }
+
+    fn weather(app: &App) -> Weather {
+        app.resources.get::<Weather>().unwrap().clone()
+    }
+
+    fn sent(app: &App) -> Vec<WeatherEvent> {
+        app.resources.get::<Sent>().unwrap().0.clone()
+    }
+
+    fn step(after: f32, weather: WeatherKind) -> WeatherStep {
+        WeatherStep {
+            after,
+            weather,
+            transition: 0.,
+        }
+    }
+
+    #[test]
+    fn changing_is_announced_before_it_is_done() {
+        let mut app = app(WeatherConfig::default(), 0.25);
+        app.resources
+            .get_mut::<Weather>()
+            .unwrap()
+            .change_to(WeatherKind::Storm, 1.);
+
+        update(&mut app, 2);
+        assert!(weather(&app).is_changing());
+        assert_eq!(
+            sent(&app),
+            vec![WeatherEvent::Changing {
+                from: WeatherKind::Rain,
+                to: WeatherKind::Storm,
+            }]
+        );
+
+        update(&mut app, 4);
+        assert!(!weather(&app).is_changing());
+        assert_eq!(weather(&app).conditions(), WeatherKind::Storm.conditions());
+        assert_eq!(
+            sent(&app),
+            vec![
+                WeatherEvent::Changing {
+                    from: WeatherKind::Rain,
+                    to: WeatherKind::Storm,
+                },
+                WeatherEvent::Changed {
+                    weather: WeatherKind::Storm,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn a_change_without_a_transition_is_done_in_one_step() {
+        let mut app = app(WeatherConfig::default(), 0.25);
+        app.resources
+            .get_mut::<Weather>()
+            .unwrap()
+            .change_to(WeatherKind::Clear, 0.);
+
+        update(&mut app, 1);
+        assert!(!weather(&app).is_changing());
+        assert_eq!(weather(&app).kind(), WeatherKind::Clear);
+        assert_eq!(weather(&app).conditions(), WeatherKind::Clear.conditions());
+
+        update(&mut app, 1);
+        assert_eq!(
+            sent(&app),
+            vec![
+                WeatherEvent::Changing {
+                    from: WeatherKind::Rain,
+                    to: WeatherKind::Clear,
+                },
+                WeatherEvent::Changed {
+                    weather: WeatherKind::Clear,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn transitions_ease_in_and_out() {
+        let mut app = app(WeatherConfig::default(), 0.25);
+        app.resources
+            .get_mut::<Weather>()
+            .unwrap()
+            .change_to(WeatherKind::Storm, 1.);
+        let (from, to) = (
+            WeatherKind::Rain.conditions(),
+            WeatherKind::Storm.conditions(),
+        );
+
+        update(&mut app, 1);
+        assert_eq!(weather(&app).conditions(), from.lerp(&to, 0.15625));
+        update(&mut app, 1);
+        assert_eq!(weather(&app).conditions(), from.lerp(&to, 0.5));
+        update(&mut app, 1);
+        assert_eq!(weather(&app).conditions(), from.lerp(&to, 0.84375));
+    }
+
+    #[test]
+    fn a_looping_schedule_starts_over() {
+        let schedule = vec![
+            step(0.5, WeatherKind::Drizzle),
+            step(0.5, WeatherKind::Storm),
+        ];
+        let mut app = app(
+            WeatherConfig {
+                schedule,
+                looping: true,
+                ..Default::default()
+            },
+            0.5,
+        );
+
+        // Each step takes one update to be waited for and another one to finish changing.
+        update(&mut app, 2);
+        assert_eq!(weather(&app).kind(), WeatherKind::Drizzle);
+        update(&mut app, 2);
+        assert_eq!(weather(&app).kind(), WeatherKind::Storm);
+        update(&mut app, 2);
+        assert_eq!(weather(&app).kind(), WeatherKind::Drizzle);
+        assert!(!weather(&app).is_changing());
+    }
+
+    #[test]
+    fn a_schedule_that_does_not_loop_stops_at_its_end() {
+        let schedule = vec![
+            step(0.5, WeatherKind::Drizzle),
+            step(0.5, WeatherKind::Storm),
+        ];
+        let mut app = app(
+            WeatherConfig {
+                schedule,
+                looping: false,
+                ..Default::default()
+            },
+            0.5,
+        );
+
+        update(&mut app, 4);
+        assert_eq!(weather(&app).kind(), WeatherKind::Storm);
+        update(&mut app, 20);
+        assert_eq!(weather(&app).kind(), WeatherKind::Storm);
+        assert!(!weather(&app).is_changing());
+        let changes = sent(&app)
+            .into_iter()
+            .filter(|event| matches!(event, WeatherEvent::Changed { .. }))
+            .count();
+        assert_eq!(changes, 2);
+    }
+}
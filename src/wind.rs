@@ -0,0 +1,92 @@
+use crate::{RainConfig, Weather};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The wind that blows the drops sideways.
+///
+/// It can be changed at any time to change the direction of the rain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Wind {
+    /// Where the wind blows to: -1 is to the left and 1 is to the right.
+    pub direction: f32,
+    /// The speed of the wind in pixels per second without any gusts.
+    pub strength: f32,
+    /// The most speed a gust adds to or takes away from the strength.
+    pub gustiness: f32,
+    /// About how many gusts there are per second.
+    pub gust_frequency: f32,
+    #[serde(skip)]
+    speed: f32,
+    #[serde(skip)]
+    time: f32,
+}
+
+impl Wind {
+    pub fn new(direction: f32, strength: f32, gustiness: f32, gust_frequency: f32) -> Self {
+        Self {
+            direction,
+            strength,
+            gustiness,
+            gust_frequency,
+            speed: direction * strength,
+            time: 0.,
+        }
+    }
+
+    /// The current horizontal speed of the wind including gusts.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// The rotation a drop falling with `velocity` needs to point in the direction it falls to.
+    pub fn tilt(velocity: Vec2) -> Quat {
+        Quat::from_rotation_z(Self::angle(velocity))
+    }
+
+    /// The angle around the z axis of `tilt`.
+    pub fn angle(velocity: Vec2) -> f32 {
+        velocity.x.atan2(-velocity.y)
+    }
+}
+
+/// Winds are equal if they are configured the same, however the gusts are blowing right now.
+impl PartialEq for Wind {
+    fn eq(&self, other: &Self) -> bool {
+        self.direction == other.direction
+            && self.strength == other.strength
+            && self.gustiness == other.gustiness
+            && self.gust_frequency == other.gust_frequency
+    }
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self::new(-1., 300., 150., 0.5)
+    }
+}
+
+/// Smooth noise between -1 and 1 that changes by about one whole value per unit of `t`.
+fn noise(t: f32) -> f32 {
+    fn hash(n: i32) -> f32 {
+        let mut n = n as u32;
+        n = (n << 13) ^ n;
+        n = n
+            .wrapping_mul(n.wrapping_mul(n).wrapping_mul(15731).wrapping_add(789221))
+            .wrapping_add(1376312589);
+        (n & 0x7fffffff) as f32 / 0x7fffffff as f32 * 2. - 1.
+    }
+
+    let i = t.floor();
+    let f = t - i;
+    let smooth = f * f * (3. - 2. * f);
+    let a = hash(i as i32);
+    let b = hash(i as i32 + 1);
+    a + (b - a) * smooth
+}
+
+pub(crate) fn blow_wind(mut wind: ResMut<Wind>, weather: Res<Weather>, config: Res<RainConfig>) {
+    wind.time += config.timestep;
+    let gust = wind.gustiness * noise(wind.time * wind.gust_frequency);
+    wind.speed = wind.direction * (wind.strength + gust) * weather.conditions().wind;
+}
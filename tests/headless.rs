@@ -0,0 +1,191 @@
+use bevy::{prelude::*, window::WindowId};
+use rain::{
+    Collider, Drop, DropImpact, DropPool, Intensity, Position, RainArea, RainAreaBundle,
+    RainConfig, RainPlugin, SkyGradient, Viewport, SIMULATION_STAGE,
+};
+use std::{thread, time::Duration};
+
+fn config() -> RainConfig {
+    RainConfig {
+        intensity: Intensity::Downpour,
+        seed: Some(7),
+        ..Default::default()
+    }
+}
+
+fn app(config: RainConfig) -> App {
+    let mut app = App::build();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(RainPlugin::headless(config, Viewport::new(640., 480.)));
+    app.app
+}
+
+/// Updates `app` for `frames` frames, giving the fixed timestep of the simulation time to pass.
+fn run(app: &mut App, frames: usize) {
+    for _ in 0..frames {
+        thread::sleep(Duration::from_millis(5));
+        app.update();
+    }
+}
+
+#[test]
+fn areas_wait_for_their_window() {
+    let mut app = app(config());
+    let window = WindowId::new();
+    let area = app.world.spawn(RainAreaBundle {
+        viewport: Viewport::new(640., 480.),
+        ..RainAreaBundle::window(window, config(), Vec2::zero())
+    });
+
+    run(&mut app, 50);
+    assert!(app.world.get::<RainArea>(area).unwrap().is_paused());
+    assert!(app.world.get::<DropPool>(area).unwrap().is_empty());
+
+    app.resources.get_mut::<Windows>().unwrap().add(Window::new(
+        window,
+        &WindowDescriptor::default(),
+        640,
+        480,
+        1.,
+    ));
+    run(&mut app, 50);
+    assert!(!app.world.get::<RainArea>(area).unwrap().is_paused());
+    assert!(!app.world.get::<DropPool>(area).unwrap().is_empty());
+}
+
+#[test]
+fn areas_spawn_no_more_than_max_drops() {
+    let mut app = app(RainConfig {
+        intensity: Intensity::Density(f32::MAX),
+        max_drops: 100,
+        ..config()
+    });
+
+    run(&mut app, 20);
+    for pool in app.world.query::<&DropPool>() {
+        assert!(pool.active() > 0);
+        assert!(pool.len() <= 100);
+    }
+}
+
+#[test]
+fn areas_share_the_settings_of_the_plugin() {
+    let area = RainAreaBundle::headless(
+        RainConfig {
+            intensity: Intensity::Drizzle,
+            seed: Some(3),
+            sky: SkyGradient::new(Color::BLACK, Color::WHITE),
+            ..Default::default()
+        },
+        Viewport::new(640., 480.),
+    );
+    assert_eq!(area.config.intensity, Intensity::Drizzle);
+    assert_eq!(area.config.seed, None);
+    assert_eq!(area.config.sky, SkyGradient::default());
+}
+
+/// The positions of all drops after a number of steps of the simulation.
+#[derive(Default)]
+struct Snapshot {
+    steps: usize,
+    positions: Option<Vec<Vec2>>,
+}
+
+const SNAPSHOT_STEPS: usize = 120;
+
+fn take_snapshot(mut snapshot: ResMut<Snapshot>, drops: Query<&Position, With<Drop>>) {
+    snapshot.steps += 1;
+    if snapshot.steps == SNAPSHOT_STEPS {
+        snapshot.positions = Some(drops.iter().map(|position| position.current).collect());
+    }
+}
+
+fn snapshot(config: RainConfig) -> Vec<Vec2> {
+    let mut app = App::build();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(RainPlugin::headless(config, Viewport::new(640., 480.)))
+        .init_resource::<Snapshot>()
+        .add_system_to_stage(SIMULATION_STAGE, take_snapshot.system());
+    let mut app = app.app;
+    while app.resources.get::<Snapshot>().unwrap().positions.is_none() {
+        run(&mut app, 1);
+    }
+    let snapshot = app.resources.get::<Snapshot>().unwrap();
+    snapshot.positions.clone().unwrap()
+}
+
+#[test]
+fn the_same_seed_makes_the_same_rain() {
+    let first = snapshot(config());
+    let second = snapshot(config());
+    assert!(!first.is_empty());
+    assert_eq!(first, second);
+}
+
+#[derive(Default)]
+struct Impacts(Vec<DropImpact>);
+
+fn collect_impacts(mut impacts: ResMut<Impacts>, mut events: EventReader<DropImpact>) {
+    impacts.0.extend(events.iter().copied());
+}
+
+#[test]
+fn drops_fall_and_are_retired_at_the_bottom() {
+    let viewport = Viewport::new(640., 480.);
+    let mut app = App::build();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(RainPlugin::headless(config(), viewport))
+        .init_resource::<Impacts>()
+        .add_system(collect_impacts.system());
+    let mut app = app.app;
+
+    while app.resources.get::<Impacts>().unwrap().0.is_empty() {
+        run(&mut app, 1);
+    }
+    for pool in app.world.query::<&DropPool>() {
+        assert!(!pool.is_empty());
+    }
+    for impact in &app.resources.get::<Impacts>().unwrap().0 {
+        assert_eq!(impact.collider, None);
+        assert_eq!(impact.position.y, viewport.bottom());
+        assert!(impact.velocity.y < 0.);
+    }
+    for (drop, position) in app.world.query::<(&Drop, &Position)>() {
+        if drop.is_active() {
+            assert!(position.current.y >= viewport.bottom());
+            assert!(position.current.y <= position.previous.y);
+        }
+    }
+}
+
+#[test]
+fn drops_hit_the_nearest_of_several_colliders() {
+    let mut app = App::build();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(RainPlugin::headless(config(), Viewport::new(640., 480.)))
+        .init_resource::<Impacts>()
+        .add_system(collect_impacts.system());
+    let mut app = app.app;
+    // Without the `TransformPlugin` the global transforms are not updated, so they are set here.
+    // The far box starts only 10 pixels below the near one, so both are often crossed in one step.
+    app.world.spawn((
+        Collider::Box {
+            half_size: Vec2::new(1000., 200.),
+        },
+        GlobalTransform::from_translation(Vec3::new(0., -100., 0.)),
+    ));
+    let near = app.world.spawn((
+        Collider::Box {
+            half_size: Vec2::new(1000., 10.),
+        },
+        GlobalTransform::from_translation(Vec3::new(0., 100., 0.)),
+    ));
+
+    while app.resources.get::<Impacts>().unwrap().0.len() < 20 {
+        run(&mut app, 1);
+    }
+    for impact in &app.resources.get::<Impacts>().unwrap().0 {
+        assert_eq!(impact.collider, Some(near));
+        assert!((impact.position.y - 110.).abs() < 0.01);
+    }
+}